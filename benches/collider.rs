@@ -0,0 +1,155 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use goblin_game::{
+    collider::{Collider, CollisionFilter},
+    components::{collider_shape::ColliderShape, collision_layers::CollisionLayers},
+    ecs::{Ecs, Entity},
+    narrow_phase::PlacedShape,
+    ray::Ray,
+};
+use nalgebra_glm::{self as glm, Mat4, Vec3};
+
+const BOX_COUNTS: [usize; 3] = [100, 1000, 10_000];
+/// Distance between neighbouring boxes of the grid.
+const SPACING: f32 = 3.0;
+
+fn placement(index: usize, side: usize) -> Mat4 {
+    let x = (index % side) as f32 * SPACING;
+    let z = (index / side) as f32 * SPACING;
+    glm::translation(&Vec3::new(x, 0.0, z))
+}
+
+/// A square grid of `box_count` unit cubes on the ground, and the entities they belong to.
+fn world(box_count: usize) -> (Collider, Vec<Entity>) {
+    let mut ecs = Ecs::with_capacity(box_count);
+    let mut collider = Collider::new();
+    let side = (box_count as f32).sqrt().ceil() as usize;
+    let shape = ColliderShape::cube();
+
+    let entities: Vec<Entity> = (0..box_count)
+        .map(|index| {
+            let entity = ecs.create_entity().expect("Could not create entity");
+            collider.add_collidable(
+                entity,
+                &shape,
+                CollisionLayers::DEFAULT,
+                &placement(index, side),
+            );
+            entity
+        })
+        .collect();
+
+    (collider, entities)
+}
+
+/// Points spread over the grid, above it.
+fn probes(box_count: usize) -> Vec<Vec3> {
+    let extent = (box_count as f32).sqrt() * SPACING;
+    (0..64)
+        .map(|index| {
+            let along = (index % 8) as f32 / 8.0 * extent;
+            let across = (index / 8) as f32 / 8.0 * extent;
+            Vec3::new(along + 0.5, 2.0, across + 0.5)
+        })
+        .collect()
+}
+
+fn raycast(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collider_raycast");
+    let filter = CollisionFilter::new();
+
+    for box_count in BOX_COUNTS {
+        let (collider, _) = world(box_count);
+        let probes = probes(box_count);
+        group.bench_with_input(BenchmarkId::new("down", box_count), &probes, |b, probes| {
+            b.iter(|| {
+                for origin in probes.iter() {
+                    black_box(collider.raycast(*origin, -Vec3::y(), 10.0, &filter));
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("across", box_count),
+            &probes,
+            |b, probes| {
+                b.iter(|| {
+                    for origin in probes.iter() {
+                        let origin = Vec3::new(origin.x, 0.0, origin.z);
+                        black_box(collider.raycast(origin, Vec3::x(), 50.0, &filter));
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// What `PhysicsSystem` asks for each body every tick: contacts and six short rays.
+fn body_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collider_body_tick");
+    let directions = [
+        Vec3::x(),
+        -Vec3::x(),
+        Vec3::y(),
+        -Vec3::y(),
+        Vec3::z(),
+        -Vec3::z(),
+    ];
+
+    for box_count in BOX_COUNTS {
+        let (collider, _) = world(box_count);
+        let probes = probes(box_count);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(box_count),
+            &probes,
+            |b, probes| {
+                b.iter(|| {
+                    for center in probes.iter() {
+                        let center = center - Vec3::new(0.0, 1.5, 0.0);
+                        let sphere = PlacedShape::Sphere {
+                            center,
+                            radius: 0.5,
+                        };
+                        black_box(collider.contacts(&sphere));
+                        for direction in directions {
+                            black_box(collider.collides(&Ray::new(center, direction)));
+                        }
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn update_body(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collider_update_body");
+    let shape = ColliderShape::cube();
+
+    for box_count in BOX_COUNTS {
+        let (mut collider, entities) = world(box_count);
+        let side = (box_count as f32).sqrt().ceil() as usize;
+        let mut tick = 0;
+
+        group.bench_function(BenchmarkId::from_parameter(box_count), |b| {
+            b.iter(|| {
+                tick += 1;
+                let lift = glm::translation(&Vec3::new(0.0, tick as f32 * 0.01, 0.0));
+                for (index, entity) in entities.iter().enumerate().take(100) {
+                    collider.update_body(
+                        *entity,
+                        &shape,
+                        CollisionLayers::DEFAULT,
+                        &(lift * placement(index, side)),
+                    );
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, raycast, body_tick, update_body);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use goblin_game::ecs::{Ecs, Entity};
+
+const ENTITY_COUNT: usize = 1000;
+const TYPE_COUNTS: [usize; 2] = [1, 50];
+
+macro_rules! component_types {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[allow(dead_code)]
+            struct $name(u32);
+        )*
+
+        /// Registers the first `count` of the declared component types.
+        fn register_types(ecs: &mut Ecs, count: usize) {
+            let registrations: [fn(&mut Ecs); 50] = [$(
+                |ecs| ecs.register_component::<$name>().expect("Could not register component"),
+            )*];
+            for register in registrations.iter().take(count) {
+                register(ecs);
+            }
+        }
+    };
+}
+
+component_types!(
+    C0, C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20,
+    C21, C22, C23, C24, C25, C26, C27, C28, C29, C30, C31, C32, C33, C34, C35, C36, C37, C38, C39,
+    C40, C41, C42, C43, C44, C45, C46, C47, C48, C49,
+);
+
+/// Entities holding `C0`, in a world with `type_count` registered component types.
+fn world(type_count: usize) -> (Ecs, Vec<Entity>) {
+    let mut ecs = Ecs::with_capacity(ENTITY_COUNT);
+    register_types(&mut ecs, type_count);
+
+    let entities = (0..ENTITY_COUNT)
+        .map(|index| {
+            ecs.spawn((C0(index as u32),))
+                .expect("Could not spawn entity")
+        })
+        .collect();
+
+    (ecs, entities)
+}
+
+fn lookups(c: &mut Criterion) {
+    let mut group = c.benchmark_group("component_type_lookup");
+
+    for type_count in TYPE_COUNTS {
+        let (mut ecs, entities) = world(type_count);
+
+        group.bench_function(BenchmarkId::new("add_component", type_count), |b| {
+            b.iter(|| {
+                for entity in entities.iter() {
+                    ecs.add_component(*entity, C0(black_box(1)))
+                        .expect("Could not add component");
+                }
+            })
+        });
+        group.bench_function(BenchmarkId::new("get_component", type_count), |b| {
+            b.iter(|| {
+                for entity in entities.iter() {
+                    black_box(
+                        ecs.get_component::<C0>(*entity)
+                            .expect("Could not get component"),
+                    );
+                }
+            })
+        });
+        group.bench_function(BenchmarkId::new("get_component_vec", type_count), |b| {
+            b.iter(|| {
+                for _ in 0..ENTITY_COUNT {
+                    let storage = ecs
+                        .get_component_vec::<C0>()
+                        .expect("Could not get component vec");
+                    black_box(&storage);
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, lookups);
+criterion_main!(benches);
@@ -0,0 +1,101 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use goblin_game::{
+    components::{rigid_body::RigidBody, transform::Transform},
+    ecs::{Ecs, StorageType},
+};
+use nalgebra_glm::Vec3;
+
+const ENTITY_COUNTS: [usize; 3] = [100, 5000, 100_000];
+const LAYOUTS: [(&str, StorageType); 2] =
+    [("dense", StorageType::Dense), ("table", StorageType::Table)];
+
+/// Every entity gets a `Transform`, every `body_every`th one also a `RigidBody`.
+fn world(storage_type: StorageType, entity_count: usize, body_every: usize) -> Ecs {
+    let mut ecs = Ecs::with_capacity(entity_count);
+    ecs.set_default_storage(storage_type);
+    ecs.register_component::<Transform>()
+        .expect("Could not register component");
+    ecs.register_component::<RigidBody>()
+        .expect("Could not register component");
+
+    for index in 0..entity_count {
+        let transform = Transform::new(Vec3::new(index as f32, 0.0, 0.0), None, None);
+        if index % body_every == 0 {
+            ecs.spawn((transform, RigidBody::default()))
+                .expect("Could not spawn entity");
+        } else {
+            ecs.spawn((transform,)).expect("Could not spawn entity");
+        }
+    }
+
+    ecs
+}
+
+fn step(ecs: &Ecs) {
+    let mut query = ecs
+        .query::<(&mut RigidBody, &mut Transform)>()
+        .expect("Could not query rigid bodies");
+
+    for (_, (mut rigid_body, mut transform)) in query.iter() {
+        rigid_body.apply_force(Vec3::new(0.0, -0.001, 0.0));
+        let position = transform.position() + rigid_body.velocity() + rigid_body.net_force();
+        transform.translate(black_box(position));
+    }
+}
+
+fn bench_iteration(c: &mut Criterion, name: &str, body_every: usize) {
+    let mut group = c.benchmark_group(name);
+
+    for entity_count in ENTITY_COUNTS {
+        for (layout, storage_type) in LAYOUTS {
+            let ecs = world(storage_type, entity_count, body_every);
+            group.bench_with_input(BenchmarkId::new(layout, entity_count), &ecs, |b, ecs| {
+                b.iter(|| step(ecs))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn all_bodies(c: &mut Criterion) {
+    bench_iteration(c, "physics_all_bodies", 1);
+}
+
+fn few_bodies(c: &mut Criterion) {
+    bench_iteration(c, "physics_few_bodies", 100);
+}
+
+fn insert_remove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_remove");
+
+    for entity_count in ENTITY_COUNTS {
+        for (layout, storage_type) in LAYOUTS {
+            let mut ecs = world(storage_type, entity_count, 1);
+            let entities: Vec<_> = ecs
+                .query::<&Transform>()
+                .expect("Could not query transforms")
+                .iter()
+                .map(|(entity, _)| entity)
+                .collect();
+
+            group.bench_function(BenchmarkId::new(layout, entity_count), |b| {
+                b.iter(|| {
+                    for entity in entities.iter() {
+                        ecs.remove_component::<RigidBody>(*entity)
+                            .expect("Could not remove component");
+                    }
+                    for entity in entities.iter() {
+                        ecs.add_component(*entity, RigidBody::default())
+                            .expect("Could not add component");
+                    }
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, all_bodies, few_bodies, insert_remove);
+criterion_main!(benches);
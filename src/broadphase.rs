@@ -0,0 +1,278 @@
+use crate::{constants::MAX_CELLS_PER_ENTRY, ecs::Entity};
+use nalgebra_glm::{self as glm, Vec3};
+use std::collections::{HashMap, HashSet};
+
+type Cell = (i32, i32, i32);
+
+/// Axis-aligned box between two corners.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Bounds around every point.
+    pub fn around(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(
+            Self::new(Vec3::repeat(f32::MAX), Vec3::repeat(f32::MIN)),
+            |bounds, point| {
+                Self::new(
+                    glm::min2(&bounds.min, &point),
+                    glm::max2(&bounds.max, &point),
+                )
+            },
+        )
+    }
+
+    pub fn overlaps(&self, other: &Bounds) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds::new(
+            glm::min2(&self.min, &other.min),
+            glm::max2(&self.max, &other.max),
+        )
+    }
+
+    /// The distances along the ray at which it enters and leaves the bounds, if it does.
+    fn clip(&self, origin: Vec3, direction: Vec3) -> Option<(f32, f32)> {
+        let mut enter = 0.0_f32;
+        let mut exit = f32::MAX;
+
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < self.min[axis] || origin[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let to_min = (self.min[axis] - origin[axis]) / direction[axis];
+            let to_max = (self.max[axis] - origin[axis]) / direction[axis];
+            enter = enter.max(to_min.min(to_max));
+            exit = exit.min(to_min.max(to_max));
+        }
+
+        (enter <= exit).then_some((enter, exit))
+    }
+}
+
+struct Entry {
+    bounds: Bounds,
+    /// First and last cell the bounds cover, `None` for entries covering too many cells.
+    cells: Option<(Cell, Cell)>,
+}
+
+/// Uniform grid finding the entities whose bounds are near a point, box or ray, without
+/// looking at the rest. Entities covering more than `MAX_CELLS_PER_ENTRY` cells, such as
+/// floors, are kept aside and always returned.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<Entity>>,
+    entries: HashMap<Entity, Entry>,
+    oversized: HashSet<Entity>,
+    /// Bounds of everything ever inserted, so rays stop once they leave it.
+    extent: Option<Bounds>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+            oversized: HashSet::new(),
+            extent: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn cell(&self, point: Vec3) -> Cell {
+        let cell = (point / self.cell_size).map(|value| value.floor() as i32);
+        (cell.x, cell.y, cell.z)
+    }
+
+    fn cell_range(&self, bounds: &Bounds) -> (Cell, Cell) {
+        (self.cell(bounds.min), self.cell(bounds.max))
+    }
+
+    fn cell_count((first, last): (Cell, Cell)) -> usize {
+        let span = |from: i32, to: i32| (to as i64 - from as i64 + 1).max(0) as usize;
+        span(first.0, last.0)
+            .saturating_mul(span(first.1, last.1))
+            .saturating_mul(span(first.2, last.2))
+    }
+
+    fn cells_in((first, last): (Cell, Cell)) -> impl Iterator<Item = Cell> {
+        (first.0..=last.0).flat_map(move |x| {
+            (first.1..=last.1).flat_map(move |y| (first.2..=last.2).map(move |z| (x, y, z)))
+        })
+    }
+
+    /// Adds the entity or moves it to `bounds`. Only touches the grid if the cells it covers
+    /// changed.
+    pub fn insert(&mut self, entity: Entity, bounds: Bounds) {
+        let range = self.cell_range(&bounds);
+        let cells = (Self::cell_count(range) <= MAX_CELLS_PER_ENTRY).then_some(range);
+        self.extent = Some(self.extent.map_or(bounds, |extent| extent.union(&bounds)));
+
+        if let Some(entry) = self.entries.get_mut(&entity) {
+            if entry.cells == cells {
+                entry.bounds = bounds;
+                return;
+            }
+            self.remove(entity);
+        }
+
+        match cells {
+            Some(range) => {
+                for cell in Self::cells_in(range) {
+                    self.cells.entry(cell).or_default().push(entity);
+                }
+            }
+            None => {
+                self.oversized.insert(entity);
+            }
+        }
+        self.entries.insert(entity, Entry { bounds, cells });
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let Some(entry) = self.entries.remove(&entity) else {
+            return;
+        };
+
+        match entry.cells {
+            Some(range) => {
+                for cell in Self::cells_in(range) {
+                    if let Some(entities) = self.cells.get_mut(&cell) {
+                        entities.retain(|other| *other != entity);
+                        if entities.is_empty() {
+                            self.cells.remove(&cell);
+                        }
+                    }
+                }
+            }
+            None => {
+                self.oversized.remove(&entity);
+            }
+        }
+    }
+
+    /// Every entity whose bounds overlap `bounds`.
+    pub fn query(&self, bounds: &Bounds) -> Vec<Entity> {
+        let mut found: HashSet<Entity> = HashSet::new();
+        let range = self.cell_range(bounds);
+
+        // Large queries are cheaper by walking the occupied cells than the covered ones.
+        if Self::cell_count(range) > self.cells.len() {
+            let (first, last) = range;
+            for (cell, entities) in self.cells.iter() {
+                if (first.0..=last.0).contains(&cell.0)
+                    && (first.1..=last.1).contains(&cell.1)
+                    && (first.2..=last.2).contains(&cell.2)
+                {
+                    found.extend(entities.iter().copied());
+                }
+            }
+        } else {
+            for cell in Self::cells_in(range) {
+                if let Some(entities) = self.cells.get(&cell) {
+                    found.extend(entities.iter().copied());
+                }
+            }
+        }
+        found.extend(self.oversized.iter().copied());
+
+        found
+            .into_iter()
+            .filter(|entity| self.entries[entity].bounds.overlaps(bounds))
+            .collect()
+    }
+
+    /// Every entity whose bounds the ray passes through within `max_distance` lengths of
+    /// `direction`, walking the cells along the ray.
+    pub fn query_ray(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Vec<Entity> {
+        let mut found: HashSet<Entity> = self.oversized.clone();
+
+        let clipped = self
+            .extent
+            .and_then(|extent| extent.clip(origin, direction))
+            .filter(|(enter, _)| *enter <= max_distance);
+        if let Some((enter, exit)) = clipped {
+            let start = origin + direction * enter;
+            let length = exit.min(max_distance) - enter;
+            self.walk_cells(start, direction, length, |entities| {
+                found.extend(entities.iter().copied())
+            });
+        }
+
+        found
+            .into_iter()
+            .filter(|entity| {
+                self.entries[entity]
+                    .bounds
+                    .clip(origin, direction)
+                    .is_some_and(|(enter, _)| enter <= max_distance)
+            })
+            .collect()
+    }
+
+    /// Visits the entities of every occupied cell the ray from `start` passes through within
+    /// `length`, see Amanatides and Woo's "A Fast Voxel Traversal Algorithm".
+    fn walk_cells(
+        &self,
+        start: Vec3,
+        direction: Vec3,
+        length: f32,
+        mut visit: impl FnMut(&[Entity]),
+    ) {
+        let mut cell = self.cell(start);
+        let mut step = [0; 3];
+        let mut next = [f32::MAX; 3];
+        let mut delta = [f32::MAX; 3];
+
+        let first = [cell.0, cell.1, cell.2];
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                continue;
+            }
+            step[axis] = direction[axis].signum() as i32;
+            let boundary = (first[axis] + (step[axis] > 0) as i32) as f32 * self.cell_size;
+            next[axis] = (boundary - start[axis]) / direction[axis];
+            delta[axis] = self.cell_size / direction[axis].abs();
+        }
+
+        loop {
+            if let Some(entities) = self.cells.get(&cell) {
+                visit(entities);
+            }
+
+            let axis = (0..3)
+                .min_by(|a, b| next[*a].total_cmp(&next[*b]))
+                .expect("There are three axes");
+            if next[axis] > length {
+                return;
+            }
+            match axis {
+                0 => cell.0 += step[0],
+                1 => cell.1 += step[1],
+                _ => cell.2 += step[2],
+            }
+            next[axis] += delta[axis];
+        }
+    }
+}
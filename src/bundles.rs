@@ -0,0 +1,101 @@
+use crate::{
+    components::{
+        camera_followable::CameraFollowable,
+        collider_shape::ColliderShape,
+        controllable::Controllable,
+        global_transform::GlobalTransform,
+        gravity::GravityComponent,
+        mesh::MeshComponent,
+        rigid_body::RigidBody,
+        static_collider::StaticCollider,
+        tags::{Player, Static},
+        transform::Transform,
+    },
+    constants::{PLAYER_HEIGHT, PLAYER_RADIUS},
+    ecs::{Bundle, Ecs, EcsError, Entity, Name},
+    mesh_manager::MeshId,
+};
+use nalgebra_glm::Vec3;
+
+/// The controllable, camera-followed player body, named `"player"`.
+pub struct PlayerBundle {
+    pub transform: Transform,
+    pub controllable: Controllable,
+    pub rigid_body: RigidBody,
+    pub gravity: GravityComponent,
+    pub camera_followable: CameraFollowable,
+    pub collider: ColliderShape,
+}
+
+impl PlayerBundle {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            transform: Transform::new(position, None, None),
+            controllable: Controllable::new(),
+            rigid_body: RigidBody::new(PLAYER_HEIGHT, PLAYER_RADIUS),
+            gravity: GravityComponent::default(),
+            camera_followable: CameraFollowable::new(true, Vec3::new(0.0, 1.0, 0.0)),
+            collider: ColliderShape::capsule(PLAYER_HEIGHT, PLAYER_RADIUS),
+        }
+    }
+}
+
+impl Bundle for PlayerBundle {
+    fn insert(self, ecs: &mut Ecs, entity: Entity) -> Result<(), EcsError> {
+        (
+            Name::new("player"),
+            Player,
+            self.transform,
+            GlobalTransform::default(),
+            self.controllable,
+            self.rigid_body,
+            self.gravity,
+            self.camera_followable,
+            self.collider,
+        )
+            .insert(ecs, entity)
+    }
+}
+
+/// A rendered mesh that never moves on its own, such as floors, walls and blocks. Props with a
+/// collider are solid.
+pub struct StaticPropBundle {
+    pub name: Name,
+    pub mesh: MeshComponent,
+    pub transform: Transform,
+    pub collider: Option<ColliderShape>,
+}
+
+impl StaticPropBundle {
+    pub fn new(name: &str, mesh_id: MeshId, transform: Transform) -> Self {
+        Self {
+            name: Name::new(name),
+            mesh: MeshComponent { id: mesh_id },
+            transform,
+            collider: None,
+        }
+    }
+
+    pub fn with_collider(mut self, collider: ColliderShape) -> Self {
+        self.collider = Some(collider);
+        self
+    }
+}
+
+impl Bundle for StaticPropBundle {
+    fn insert(self, ecs: &mut Ecs, entity: Entity) -> Result<(), EcsError> {
+        (
+            self.name,
+            Static,
+            self.mesh,
+            self.transform,
+            GlobalTransform::default(),
+        )
+            .insert(ecs, entity)?;
+
+        match self.collider {
+            Some(collider) => (collider, StaticCollider).insert(ecs, entity),
+            None => Ok(()),
+        }
+    }
+}
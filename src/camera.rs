@@ -1,5 +1,5 @@
 use crate::{
-    constants::{SCREEN_HEIGHT, SCREEN_WIDTH, WORLD_UP},
+    constants::{CAMERA_FOV, SCREEN_HEIGHT, SCREEN_WIDTH, WORLD_UP},
     utils::degree_to_radian,
 };
 use nalgebra_glm::{self as glm, Mat4, Vec3};
@@ -10,7 +10,7 @@ pub struct Camera {
 
 impl Camera {
     pub fn new() -> Self {
-        Self { fov: 45.0 }
+        Self { fov: CAMERA_FOV }
     }
 
     pub fn view_transform(position: &Vec3, front: &Vec3) -> Mat4 {
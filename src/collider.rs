@@ -1,83 +1,554 @@
 use crate::{
-    components::transform::Transform,
-    constants::COLLISION_RANGE,
-    models::plane::Plane,
-    ray::{Intersection, Ray},
-    utils::{create_transform_matrix, point_in_triangle},
+    broadphase::{Bounds, SpatialHash},
+    components::{collider_shape::ColliderShape, collision_layers::CollisionLayers},
+    constants::{BROADPHASE_CELL_SIZE, COLLISION_RANGE, SHAPE_CAST_REFINE_STEPS},
+    ecs::Entity,
+    narrow_phase::{contact, Contact, PlacedShape},
+    ray::Ray,
 };
-use itertools::Itertools;
-use nalgebra_glm::{self as glm, Mat4, Vec3, Vec4};
+use nalgebra_glm::{Mat4, Vec3};
+use std::collections::HashMap;
 
+struct Collidable {
+    /// The shape and matrix it was placed from, to tell whether it needs placing again.
+    source: (ColliderShape, Mat4),
+    shape: PlacedShape,
+    layers: CollisionLayers,
+}
+
+/// Collidables with their shapes cached in world space and hashed by where they are.
+struct Collidables {
+    entries: HashMap<Entity, Collidable>,
+    hash: SpatialHash,
+}
+
+impl Collidables {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            hash: SpatialHash::new(BROADPHASE_CELL_SIZE),
+        }
+    }
+
+    /// Places the entity's shape, unless it is already placed from the same shape and matrix.
+    fn insert(
+        &mut self,
+        entity: Entity,
+        shape: &ColliderShape,
+        layers: CollisionLayers,
+        transform: &Mat4,
+    ) {
+        if let Some(collidable) = self.entries.get_mut(&entity) {
+            if collidable.source.1 == *transform && collidable.source.0 == *shape {
+                collidable.layers = layers;
+                return;
+            }
+        }
+
+        let placed = shape.placed(transform);
+        self.hash.insert(entity, placed.bounds());
+        self.entries.insert(
+            entity,
+            Collidable {
+                source: (shape.clone(), *transform),
+                shape: placed,
+                layers,
+            },
+        );
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        self.entries.remove(&entity);
+        self.hash.remove(entity);
+    }
+
+    fn retain(&mut self, keep: impl Fn(Entity) -> bool) {
+        let removed: Vec<Entity> = self
+            .entries
+            .keys()
+            .copied()
+            .filter(|entity| !keep(*entity))
+            .collect();
+        for entity in removed {
+            self.remove(entity);
+        }
+    }
+
+    fn near(&self, bounds: &Bounds) -> impl Iterator<Item = (Entity, &Collidable)> {
+        self.hash
+            .query(bounds)
+            .into_iter()
+            .map(|entity| (entity, &self.entries[&entity]))
+    }
+
+    fn along(&self, ray: &Ray, max_distance: f32) -> impl Iterator<Item = (Entity, &Collidable)> {
+        self.hash
+            .query_ray(ray.origin(), ray.direction(), max_distance)
+            .into_iter()
+            .map(|entity| (entity, &self.entries[&entity]))
+    }
+}
+
+/// Which collidables a query looks at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionFilter {
+    pub layers: CollisionLayers,
+    /// Usually the entity the query is made for, so it does not find itself.
+    pub ignore: Option<Entity>,
+}
+
+impl CollisionFilter {
+    /// Looks at every collidable.
+    pub fn new() -> Self {
+        Self {
+            layers: CollisionLayers::ALL,
+            ignore: None,
+        }
+    }
+
+    pub fn with_layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    pub fn ignoring(mut self, entity: Entity) -> Self {
+        self.ignore = Some(entity);
+        self
+    }
+
+    fn accepts(&self, entity: Entity, collidable: &Collidable) -> bool {
+        self.ignore != Some(entity) && self.layers.intersects(collidable.layers)
+    }
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a query hit. `distance` is in world units along the query's direction, and `normal`
+/// points out of the hit surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub entity: Entity,
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+/// Collision geometry of the world: static collidables, and rigid bodies with a
+/// `ColliderShape` as of the latest physics tick. Shapes are placed in world space once per
+/// move, and queries only look at those near them.
 pub struct Collider {
-    hit_plane: Vec<(Vec3, Vec3)>,
-    collidables: Vec<Transform>,
+    collidables: Collidables,
+    bodies: Collidables,
 }
 
 impl Collider {
     pub fn new() -> Self {
-        let hit_plane = Plane::get_indexed_vertices();
-
         Self {
-            collidables: Vec::new(),
-            hit_plane,
+            collidables: Collidables::new(),
+            bodies: Collidables::new(),
         }
     }
 
-    pub fn add_collidable(&mut self, transform: Transform) {
-        self.collidables.push(transform);
+    /// Adds the entity's shape, placed by a world-space model matrix such as the one in a
+    /// `GlobalTransform`. Replaces any shape the entity already had, and does nothing if the
+    /// shape and matrix did not change.
+    pub fn add_collidable(
+        &mut self,
+        entity: Entity,
+        shape: &ColliderShape,
+        layers: CollisionLayers,
+        transform: &Mat4,
+    ) {
+        self.collidables.insert(entity, shape, layers, transform);
+    }
+
+    pub fn remove_collidable(&mut self, entity: Entity) {
+        self.collidables.remove(entity);
     }
 
-    fn get_transformed_vertices(&self, transform: &Transform) -> Vec<(Vec3, Vec3)> {
-        let rotation = match transform.rotation() {
-            None => Mat4::identity(),
-            Some(rotation) => {
-                glm::rotation(rotation.x, &Vec3::new(rotation.y, rotation.z, rotation.w))
+    /// Moves a rigid body's shape to `transform`, adding it if it is new.
+    pub fn update_body(
+        &mut self,
+        entity: Entity,
+        shape: &ColliderShape,
+        layers: CollisionLayers,
+        transform: &Mat4,
+    ) {
+        self.bodies.insert(entity, shape, layers, transform);
+    }
+
+    /// Drops every rigid body `keep` returns false for.
+    pub fn retain_bodies(&mut self, keep: impl Fn(Entity) -> bool) {
+        self.bodies.retain(keep);
+    }
+
+    /// Every static collidable `shape` overlaps, with the contact seen from `shape`.
+    pub fn contacts(&self, shape: &PlacedShape) -> Vec<(Entity, Contact)> {
+        self.collidables
+            .near(&shape.bounds())
+            .filter_map(|(entity, collidable)| Some((entity, contact(shape, &collidable.shape)?)))
+            .collect()
+    }
+
+    /// Every pair of overlapping bodies that share a layer, with the contact seen from the
+    /// first of the pair. Pairs come in the order of their entities, so ticks replay the same.
+    pub fn body_contacts(&self) -> Vec<(Entity, Entity, Contact)> {
+        let mut contacts = Vec::new();
+
+        for (entity, body) in self.bodies.entries.iter() {
+            for (other, other_body) in self.bodies.near(&body.shape.bounds()) {
+                if other.index() <= entity.index() || !body.layers.intersects(other_body.layers) {
+                    continue;
+                }
+                if let Some(contact) = contact(&body.shape, &other_body.shape) {
+                    contacts.push((*entity, other, contact));
+                }
             }
+        }
+        contacts.sort_by_key(|(entity, other, _)| (entity.index(), other.index()));
+
+        contacts
+    }
+
+    /// Whether the ray hits a static collidable within `COLLISION_RANGE` of its origin.
+    pub fn collides(&self, ray: &Ray) -> bool {
+        self.collidables
+            .along(ray, COLLISION_RANGE)
+            .any(|(_, collidable)| {
+                collidable
+                    .shape
+                    .raycast(ray)
+                    .is_some_and(|hit| hit.distance <= COLLISION_RANGE)
+            })
+    }
+
+    /// Static collidables and bodies near `bounds` that pass the filter.
+    fn near<'a>(
+        &'a self,
+        bounds: &Bounds,
+        filter: &'a CollisionFilter,
+    ) -> impl Iterator<Item = (Entity, &'a PlacedShape)> + 'a {
+        self.collidables
+            .near(bounds)
+            .chain(self.bodies.near(bounds))
+            .filter(|(entity, collidable)| filter.accepts(*entity, collidable))
+            .map(|(entity, collidable)| (entity, &collidable.shape))
+    }
+
+    /// The closest collidable the ray from `origin` hits within `max_distance`.
+    pub fn raycast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &CollisionFilter,
+    ) -> Option<RaycastHit> {
+        self.raycast_all(origin, direction, max_distance, filter)
+            .into_iter()
+            .next()
+    }
+
+    /// Every collidable the ray from `origin` hits within `max_distance`, closest first, each
+    /// with the first point it is hit at.
+    pub fn raycast_all(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &CollisionFilter,
+    ) -> Vec<RaycastHit> {
+        let Some(direction) = direction.try_normalize(f32::EPSILON) else {
+            return Vec::new();
         };
-        let transform = create_transform_matrix(transform);
-        self.hit_plane
-            .iter()
-            .map(|(position, normal)| {
-                let position = Vec4::new(position.x, position.y, position.z, 1.0);
-                let transformed_position = transform * position;
-                let normal = Vec4::new(normal.x, normal.y, normal.z, 1.0);
-                let transformed_normal = rotation * normal;
+        let ray = Ray::new(origin, direction);
 
-                (transformed_position.xyz(), transformed_normal.xyz())
+        let mut hits: Vec<RaycastHit> = self
+            .collidables
+            .along(&ray, max_distance)
+            .chain(self.bodies.along(&ray, max_distance))
+            .filter(|(entity, collidable)| filter.accepts(*entity, collidable))
+            .filter_map(|(entity, collidable)| {
+                let hit = collidable.shape.raycast(&ray)?;
+                (hit.distance <= max_distance).then_some(RaycastHit {
+                    entity,
+                    point: hit.point,
+                    normal: hit.normal,
+                    distance: hit.distance,
+                })
             })
-            .collect()
+            .collect();
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        hits
     }
 
-    pub fn collides(&self, ray: &Ray) -> bool {
-        for collidable in self.collidables.iter() {
-            let vertices = self.get_transformed_vertices(&collidable);
-
-            for mut vertex in &vertices.iter().chunks(3) {
-                let (a_position, a_normal) = vertex
-                    .next()
-                    .expect("Missing required vertex in collision mesh");
-                let (b_position, _) = vertex
-                    .next()
-                    .expect("Missing required vertex in collision mesh");
-                let (c_position, _) = vertex
-                    .next()
-                    .expect("Missing required vertex in collision mesh");
-
-                let plane_normal = a_normal;
-
-                if let Some(Intersection { distance, point }) = ray.intersects(&plane_normal) {
-                    let triangle = (a_position.clone(), b_position.clone(), c_position.clone());
-
-                    if distance <= COLLISION_RANGE
-                        && distance >= 0.0
-                        && point_in_triangle(point, triangle)
-                    {
-                        return true;
-                    }
+    /// Sweeps a sphere from `center` and returns the first collidable it touches within
+    /// `max_distance`.
+    pub fn sphere_cast(
+        &self,
+        center: Vec3,
+        radius: f32,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &CollisionFilter,
+    ) -> Option<RaycastHit> {
+        let sphere = PlacedShape::Sphere { center, radius };
+        self.shape_cast(&sphere, direction, max_distance, filter)
+    }
+
+    /// Sweeps the capsule around the segment from `start` to `end` and returns the first
+    /// collidable it touches within `max_distance`.
+    pub fn capsule_cast(
+        &self,
+        start: Vec3,
+        end: Vec3,
+        radius: f32,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &CollisionFilter,
+    ) -> Option<RaycastHit> {
+        let capsule = PlacedShape::Capsule { start, end, radius };
+        self.shape_cast(&capsule, direction, max_distance, filter)
+    }
+
+    /// Sweeps `shape` and returns the first collidable it touches within `max_distance`. The
+    /// sweep moves in steps of half the shape's thickness and then narrows down the first
+    /// touching distance, so collidables thinner than a step can be missed near the shape's
+    /// silhouette. Shapes overlapping something from the start hit it at distance zero. Only
+    /// solid shapes with some thickness can be swept.
+    pub fn shape_cast(
+        &self,
+        shape: &PlacedShape,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &CollisionFilter,
+    ) -> Option<RaycastHit> {
+        let direction = direction.try_normalize(f32::EPSILON)?;
+        let touching = |distance: f32| {
+            let moved = shape.translated(direction * distance);
+            self.near(&moved.bounds(), filter)
+                .filter_map(|(entity, other)| Some((entity, contact(&moved, other)?)))
+                .max_by(|(_, a), (_, b)| a.depth.total_cmp(&b.depth))
+        };
+
+        // Triangle meshes and flat boxes have no thickness to step by.
+        let step = shape.thickness() / 2.0;
+        if step <= f32::EPSILON {
+            return None;
+        }
+
+        let mut previous = 0.0;
+        let mut distance = 0.0;
+        loop {
+            if touching(distance).is_some() {
+                break;
+            }
+            if distance >= max_distance {
+                return None;
+            }
+            previous = distance;
+            distance = (distance + step).min(max_distance);
+        }
+
+        // The shape is free at `previous` and touches at `distance`.
+        if distance > 0.0 {
+            for _ in 0..SHAPE_CAST_REFINE_STEPS {
+                let middle = (previous + distance) / 2.0;
+                if touching(middle).is_some() {
+                    distance = middle;
+                } else {
+                    previous = middle;
                 }
             }
         }
 
-        false
+        let (entity, contact) = touching(distance)?;
+        Some(RaycastHit {
+            entity,
+            point: contact.point,
+            normal: -contact.normal,
+            distance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::Ecs;
+    use nalgebra_glm as glm;
+
+    /// Allowed error, loose enough for the narrowed down sweep distances.
+    const TOLERANCE: f32 = 1e-3;
+
+    struct Scene {
+        collider: Collider,
+        /// Unit cube at `x = 2` on its own layer.
+        glass: Entity,
+        /// Unit cube at `x = 5`.
+        wall: Entity,
+        /// Pane at `x = 10`, a tenth of a sphere cast step thick.
+        pane: Entity,
+    }
+
+    const GLASS_LAYER: CollisionLayers = CollisionLayers::layer(3);
+
+    fn scene() -> Scene {
+        let mut ecs = Ecs::new();
+        let mut collider = Collider::new();
+        let mut add = |layers: CollisionLayers, transform: Mat4| {
+            let entity = ecs.create_entity().expect("Could not create entity");
+            collider.add_collidable(entity, &ColliderShape::cube(), layers, &transform);
+            entity
+        };
+
+        // Added furthest first, so hits are not just in the order they were added.
+        let pane = add(
+            CollisionLayers::DEFAULT,
+            glm::scale(
+                &glm::translation(&Vec3::new(10.0, 0.0, 0.0)),
+                &Vec3::new(0.025, 4.0, 4.0),
+            ),
+        );
+        let wall = add(
+            CollisionLayers::DEFAULT,
+            glm::translation(&Vec3::new(5.0, 0.0, 0.0)),
+        );
+        let glass = add(GLASS_LAYER, glm::translation(&Vec3::new(2.0, 0.0, 0.0)));
+
+        Scene {
+            collider,
+            glass,
+            wall,
+            pane,
+        }
+    }
+
+    fn assert_hit(hit: Option<RaycastHit>, entity: Entity, distance: f32, point: Vec3) {
+        let hit = hit.expect("Missing hit");
+        assert_eq!(hit.entity, entity);
+        assert!((hit.distance - distance).abs() < TOLERANCE, "{hit:?}");
+        assert!((hit.point - point).norm() < TOLERANCE, "{hit:?}");
+        assert!(
+            (hit.normal - Vec3::new(-1.0, 0.0, 0.0)).norm() < TOLERANCE,
+            "{hit:?}"
+        );
+    }
+
+    #[test]
+    fn raycasts_hit_the_closest_collidable() {
+        let scene = scene();
+        let filter = CollisionFilter::new();
+
+        // Distances are in world units whatever the direction's length.
+        let hit = scene
+            .collider
+            .raycast(Vec3::zeros(), Vec3::new(2.0, 0.0, 0.0), 100.0, &filter);
+        assert_hit(hit, scene.glass, 1.5, Vec3::new(1.5, 0.0, 0.0));
+
+        let hit = scene
+            .collider
+            .raycast(Vec3::new(0.0, 0.2, 0.0), Vec3::x(), 100.0, &filter);
+        assert_hit(hit, scene.glass, 1.5, Vec3::new(1.5, 0.2, 0.0));
+
+        assert!(scene
+            .collider
+            .raycast(Vec3::zeros(), Vec3::y(), 100.0, &filter)
+            .is_none());
+        assert!(scene
+            .collider
+            .raycast(Vec3::zeros(), Vec3::zeros(), 100.0, &filter)
+            .is_none());
+    }
+
+    #[test]
+    fn raycasts_skip_filtered_collidables() {
+        let scene = scene();
+        let wall = Vec3::new(4.5, 0.0, 0.0);
+
+        let filter = CollisionFilter::new().with_layers(CollisionLayers::DEFAULT);
+        let hit = scene
+            .collider
+            .raycast(Vec3::zeros(), Vec3::x(), 100.0, &filter);
+        assert_hit(hit, scene.wall, 4.5, wall);
+
+        let filter = CollisionFilter::new().ignoring(scene.glass);
+        let hit = scene
+            .collider
+            .raycast(Vec3::zeros(), Vec3::x(), 100.0, &filter);
+        assert_hit(hit, scene.wall, 4.5, wall);
+
+        let filter = CollisionFilter::new()
+            .with_layers(GLASS_LAYER)
+            .ignoring(scene.glass);
+        assert!(scene
+            .collider
+            .raycast(Vec3::zeros(), Vec3::x(), 100.0, &filter)
+            .is_none());
+    }
+
+    #[test]
+    fn raycasts_stop_at_the_max_distance() {
+        let scene = scene();
+        let filter = CollisionFilter::new();
+        let entities = |max_distance: f32| -> Vec<Entity> {
+            scene
+                .collider
+                .raycast_all(Vec3::zeros(), Vec3::x(), max_distance, &filter)
+                .iter()
+                .map(|hit| hit.entity)
+                .collect()
+        };
+
+        assert_eq!(entities(100.0), vec![scene.glass, scene.wall, scene.pane]);
+        assert_eq!(entities(4.5), vec![scene.glass, scene.wall]);
+        assert_eq!(entities(4.4), vec![scene.glass]);
+        assert!(entities(1.4).is_empty());
+
+        let distances: Vec<f32> = scene
+            .collider
+            .raycast_all(Vec3::zeros(), Vec3::x(), 100.0, &filter)
+            .iter()
+            .map(|hit| hit.distance)
+            .collect();
+        assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn sphere_casts_hit_the_first_collidable_touched() {
+        let scene = scene();
+        let filter = CollisionFilter::new();
+
+        let hit = scene
+            .collider
+            .sphere_cast(Vec3::zeros(), 0.25, Vec3::x(), 100.0, &filter);
+        assert_hit(hit, scene.glass, 1.25, Vec3::new(1.5, 0.0, 0.0));
+
+        let filter = filter.ignoring(scene.glass);
+        let hit = scene
+            .collider
+            .sphere_cast(Vec3::zeros(), 0.25, Vec3::x(), 4.3, &filter);
+        assert_hit(hit, scene.wall, 4.25, Vec3::new(4.5, 0.0, 0.0));
+        assert!(scene
+            .collider
+            .sphere_cast(Vec3::zeros(), 0.25, Vec3::x(), 4.2, &filter)
+            .is_none());
+    }
+
+    #[test]
+    fn fast_sphere_casts_do_not_pass_through_thin_collidables() {
+        let scene = scene();
+        let filter = CollisionFilter::new();
+
+        // One long sweep with the pane far thinner than a step.
+        let hit =
+            scene
+                .collider
+                .sphere_cast(Vec3::new(6.0, 0.0, 0.0), 0.25, Vec3::x(), 1000.0, &filter);
+        assert_hit(hit, scene.pane, 3.7375, Vec3::new(9.9875, 0.0, 0.0));
     }
 }
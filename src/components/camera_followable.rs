@@ -1,5 +1,10 @@
+use crate::{
+    ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter},
+    utils::{read_vec3, write_vec3},
+};
 use nalgebra_glm::Vec3;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct CameraFollowable {
     camera_relative_position: Vec3,
     is_being_followed: bool,
@@ -21,3 +26,21 @@ impl<'a> CameraFollowable {
         self.camera_relative_position
     }
 }
+
+impl Serializable for CameraFollowable {
+    const NAME: &'static str = "CameraFollowable";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        writer.bool(self.is_being_followed);
+        write_vec3(writer, self.camera_relative_position);
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        let is_being_followed = reader.bool()?;
+        let camera_relative_position = read_vec3(reader)?;
+
+        Ok(Self::new(is_being_followed, camera_relative_position))
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter},
+    models::plane::Plane,
+    narrow_phase::PlacedShape,
+    utils::{read_vec3, write_vec3},
+};
+use nalgebra_glm::{self as glm, Mat3, Mat4, Vec3, Vec4};
+use std::sync::Arc;
+
+/// Solid shape of an entity for collisions, in the entity's local space. Sizes are scaled by
+/// the entity's scale, so a shape matching a unit mesh keeps matching it when scaled.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColliderShape {
+    /// Box that stays aligned with the world axes, grown to enclose the rotated box.
+    Aabb { half_extents: Vec3 },
+    /// Box that rotates with the entity.
+    Obb { half_extents: Vec3 },
+    /// Sphere, scaled by the entity's largest scale.
+    Sphere { radius: f32 },
+    /// Capsule along the local Y axis, `half_height` being the distance from its center to the
+    /// center of each end cap. The radius is scaled by the larger of the X and Z scales.
+    Capsule { half_height: f32, radius: f32 },
+    /// Triangles of static level geometry. Shared, so copies are cheap.
+    TriangleMesh(Arc<Vec<[Vec3; 3]>>),
+}
+
+impl ColliderShape {
+    /// Box matching the unit cube mesh.
+    pub fn cube() -> Self {
+        ColliderShape::Obb {
+            half_extents: Vec3::new(0.5, 0.5, 0.5),
+        }
+    }
+
+    /// Capsule that is `height` tall in total.
+    pub fn capsule(height: f32, radius: f32) -> Self {
+        ColliderShape::Capsule {
+            half_height: (height / 2.0 - radius).max(0.0),
+            radius,
+        }
+    }
+
+    /// Triangle mesh from vertices in groups of three, such as `Plane::get_indexed_vertices`.
+    pub fn triangle_mesh(vertices: &[(Vec3, Vec3)]) -> Self {
+        let triangles = vertices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0].0, triangle[1].0, triangle[2].0])
+            .collect();
+
+        ColliderShape::TriangleMesh(Arc::new(triangles))
+    }
+
+    /// Triangle mesh matching the unit plane mesh.
+    pub fn plane() -> Self {
+        Self::triangle_mesh(&Plane::get_indexed_vertices())
+    }
+
+    /// The shape in world space, placed by a model matrix such as the one in a
+    /// `GlobalTransform`.
+    pub fn placed(&self, transform: &Mat4) -> PlacedShape {
+        let center = transform.column(3).xyz();
+        let linear = glm::mat4_to_mat3(transform);
+        let scale = Vec3::new(
+            linear.column(0).norm(),
+            linear.column(1).norm(),
+            linear.column(2).norm(),
+        );
+
+        match self {
+            ColliderShape::Aabb { half_extents } => PlacedShape::Box {
+                center,
+                axes: Mat3::identity(),
+                half_extents: linear.abs() * half_extents,
+            },
+            ColliderShape::Obb { half_extents } => {
+                // Zero scales leave no direction to normalize, those axes stay unrotated.
+                let axes = Mat3::from_fn(|row, column| {
+                    let axis = linear.column(column);
+                    match axis.try_normalize(f32::EPSILON) {
+                        Some(axis) => axis[row],
+                        None => Mat3::identity()[(row, column)],
+                    }
+                });
+
+                PlacedShape::Box {
+                    center,
+                    axes,
+                    half_extents: half_extents.component_mul(&scale),
+                }
+            }
+            ColliderShape::Sphere { radius } => PlacedShape::Sphere {
+                center,
+                radius: radius * scale.max(),
+            },
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => {
+                let reach = linear * Vec3::new(0.0, *half_height, 0.0);
+
+                PlacedShape::Capsule {
+                    start: center - reach,
+                    end: center + reach,
+                    radius: radius * scale.x.max(scale.z),
+                }
+            }
+            ColliderShape::TriangleMesh(triangles) => PlacedShape::TriangleMesh(
+                triangles
+                    .iter()
+                    .map(|triangle| {
+                        triangle.map(|vertex| {
+                            (transform * Vec4::new(vertex.x, vertex.y, vertex.z, 1.0)).xyz()
+                        })
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Serializable for ColliderShape {
+    const NAME: &'static str = "ColliderShape";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        match self {
+            ColliderShape::Aabb { half_extents } => {
+                writer.string("aabb");
+                write_vec3(writer, *half_extents);
+            }
+            ColliderShape::Obb { half_extents } => {
+                writer.string("obb");
+                write_vec3(writer, *half_extents);
+            }
+            ColliderShape::Sphere { radius } => {
+                writer.string("sphere");
+                writer.f32(*radius);
+            }
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => {
+                writer.string("capsule");
+                writer.f32(*half_height);
+                writer.f32(*radius);
+            }
+            ColliderShape::TriangleMesh(triangles) => {
+                writer.string("mesh");
+                writer.u32(triangles.len() as u32);
+                for vertex in triangles.iter().flatten() {
+                    write_vec3(writer, *vertex);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        let kind = reader.string()?;
+
+        Ok(match kind.as_str() {
+            "aabb" => ColliderShape::Aabb {
+                half_extents: read_vec3(reader)?,
+            },
+            "obb" => ColliderShape::Obb {
+                half_extents: read_vec3(reader)?,
+            },
+            "sphere" => ColliderShape::Sphere {
+                radius: reader.f32()?,
+            },
+            "capsule" => ColliderShape::Capsule {
+                half_height: reader.f32()?,
+                radius: reader.f32()?,
+            },
+            "mesh" => {
+                let count = reader.u32()?;
+                let triangles = (0..count)
+                    .map(|_| Ok([read_vec3(reader)?, read_vec3(reader)?, read_vec3(reader)?]))
+                    .collect::<Result<_, SerializationError>>()?;
+
+                ColliderShape::TriangleMesh(Arc::new(triangles))
+            }
+            _ => return Err(SerializationError::InvalidValue(kind)),
+        })
+    }
+}
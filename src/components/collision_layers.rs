@@ -0,0 +1,51 @@
+use crate::ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter};
+use std::ops::BitOr;
+
+/// Set of up to 32 collision layers. As a component it is the layers the entity's collider is
+/// on, colliders without one are on `DEFAULT`. As a query filter it is the layers to look at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionLayers(pub u32);
+
+impl CollisionLayers {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u32::MAX);
+    pub const DEFAULT: Self = Self::layer(0);
+
+    /// The single layer `index`, from 0 to 31.
+    pub const fn layer(index: u32) -> Self {
+        Self(1 << index)
+    }
+
+    /// Whether the two sets share a layer.
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl BitOr for CollisionLayers {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl Serializable for CollisionLayers {
+    const NAME: &'static str = "CollisionLayers";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        writer.u32(self.0);
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        Ok(Self(reader.u32()?))
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
     constants::{MOUSE_SENSITIVITY, WORLD_UP},
-    utils::degree_to_radian,
+    ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter},
+    utils::{degree_to_radian, read_vec3, write_vec3},
 };
 use nalgebra_glm::{self as glm, Vec3};
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Controllable {
     forward_motion: f32,
     horizontal_motion: f32,
@@ -66,3 +68,27 @@ impl Controllable {
         self.horizontal_motion
     }
 }
+
+impl Serializable for Controllable {
+    const NAME: &'static str = "Controllable";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        writer.f32(self.forward_motion);
+        writer.f32(self.horizontal_motion);
+        writer.f32(self.yaw);
+        writer.f32(self.pitch);
+        write_vec3(writer, self.front);
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        Ok(Self {
+            forward_motion: reader.f32()?,
+            horizontal_motion: reader.f32()?,
+            yaw: reader.f32()?,
+            pitch: reader.f32()?,
+            front: read_vec3(reader)?,
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    components::transform::Transform,
+    ecs::{Ecs, EcsError, Entity, Parent},
+    utils::create_transform_matrix,
+};
+use nalgebra_glm::{Mat4, Vec3};
+
+/// World-space model matrix of an entity, computed from its own `Transform` and those of its
+/// ancestors by the transform system. Never written by anything else. The matrix from before
+/// the latest fixed tick is kept as well, so rendering can blend between the two.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlobalTransform {
+    matrix: Mat4,
+    previous: Mat4,
+}
+
+impl GlobalTransform {
+    pub fn new(matrix: Mat4) -> Self {
+        Self {
+            matrix,
+            previous: matrix,
+        }
+    }
+
+    /// World-space matrix of the space the entity's `Transform` is in, that of its parent. Root
+    /// entities, and children whose parent has no global transform yet, are placed from the
+    /// origin.
+    pub fn parent_matrix(ecs: &Ecs, entity: Entity) -> Result<Mat4, EcsError> {
+        let parent = match ecs.query::<&Parent>()?.get(entity) {
+            Ok(parent) => parent.get(),
+            Err(_) => return Ok(Mat4::identity()),
+        };
+
+        match ecs.query::<&GlobalTransform>() {
+            Ok(mut globals) => Ok(globals
+                .get(parent)
+                .map_or(Mat4::identity(), |global| global.matrix())),
+            Err(EcsError::UnregisteredComponent) => Ok(Mat4::identity()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// World-space matrix of the entity at `transform`, which may differ from its current
+    /// `GlobalTransform` until the transform system runs again.
+    pub fn world_matrix(
+        ecs: &Ecs,
+        entity: Entity,
+        transform: &Transform,
+    ) -> Result<Mat4, EcsError> {
+        Ok(Self::parent_matrix(ecs, entity)? * create_transform_matrix(transform))
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        self.matrix
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.matrix.column(3).xyz()
+    }
+
+    /// Moves to `matrix`, remembering the current one as the previous matrix.
+    pub fn update(&mut self, matrix: Mat4) {
+        self.previous = self.matrix;
+        self.matrix = matrix;
+    }
+
+    /// Whether the matrix changed during the latest tick it was updated in.
+    pub fn is_moving(&self) -> bool {
+        self.previous != self.matrix
+    }
+
+    /// Forgets the previous matrix once the entity stops moving.
+    pub fn settle(&mut self) {
+        self.previous = self.matrix;
+    }
+
+    /// Blends from the previous matrix to the current one, `alpha` running from 0 to 1. Blending
+    /// the matrices component-wise is only exact for translations, but close enough for the
+    /// small rotations between two ticks.
+    pub fn interpolated(&self, alpha: f32) -> Mat4 {
+        self.previous + (self.matrix - self.previous) * alpha
+    }
+
+    pub fn interpolated_position(&self, alpha: f32) -> Vec3 {
+        self.interpolated(alpha).column(3).xyz()
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self::new(Mat4::identity())
+    }
+}
@@ -1,5 +1,37 @@
+use crate::{
+    constants::GRAVITY,
+    ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter},
+    utils::{read_vec3, write_vec3},
+};
 use nalgebra_glm::Vec3;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct GravityComponent {
     pub force: Vec3,
 }
+
+impl Default for GravityComponent {
+    fn default() -> Self {
+        let (x, y, z) = GRAVITY;
+
+        Self {
+            force: Vec3::new(x, y, z),
+        }
+    }
+}
+
+impl Serializable for GravityComponent {
+    const NAME: &'static str = "Gravity";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        write_vec3(writer, self.force);
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        Ok(Self {
+            force: read_vec3(reader)?,
+        })
+    }
+}
@@ -1,3 +1,33 @@
+use crate::{
+    ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter},
+    mesh_manager::MeshManager,
+};
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct MeshComponent {
     pub id: u32,
 }
+
+impl Serializable for MeshComponent {
+    const NAME: &'static str = "Mesh";
+
+    fn save(&self, ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        let mesh_manager = ecs.resource::<MeshManager>()?;
+        let name = mesh_manager
+            .mesh_name(self.id)
+            .ok_or_else(|| SerializationError::UnknownAsset(self.id.to_string()))?;
+        writer.string(name);
+
+        Ok(())
+    }
+
+    fn load(ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        let name = reader.string()?;
+        let id = ecs
+            .resource::<MeshManager>()?
+            .mesh_id(&name)
+            .ok_or(SerializationError::UnknownAsset(name))?;
+
+        Ok(Self { id })
+    }
+}
@@ -1,6 +1,46 @@
+use crate::ecs::Ecs;
+
 pub mod camera_followable;
+pub mod collider_shape;
+pub mod collision_layers;
 pub mod controllable;
+pub mod global_transform;
 pub mod gravity;
 pub mod mesh;
 pub mod rigid_body;
+pub mod static_collider;
+pub mod tags;
 pub mod transform;
+
+/// Opts every game component into world snapshots.
+pub fn register_snapshots(ecs: &mut Ecs) {
+    ecs.register_snapshot::<transform::Transform>();
+    ecs.register_snapshot::<global_transform::GlobalTransform>();
+    ecs.register_snapshot::<rigid_body::RigidBody>();
+    ecs.register_snapshot::<gravity::GravityComponent>();
+    ecs.register_snapshot::<controllable::Controllable>();
+    ecs.register_snapshot::<camera_followable::CameraFollowable>();
+    ecs.register_snapshot::<mesh::MeshComponent>();
+    ecs.register_snapshot::<static_collider::StaticCollider>();
+    ecs.register_snapshot::<collider_shape::ColliderShape>();
+    ecs.register_snapshot::<collision_layers::CollisionLayers>();
+    ecs.register_snapshot::<tags::Player>();
+    ecs.register_snapshot::<tags::Enemy>();
+    ecs.register_snapshot::<tags::Static>();
+}
+
+/// Opts every game component that can be saved into world serialization.
+pub fn register_serializable(ecs: &mut Ecs) {
+    ecs.register_serializable::<transform::Transform>();
+    ecs.register_serializable::<rigid_body::RigidBody>();
+    ecs.register_serializable::<gravity::GravityComponent>();
+    ecs.register_serializable::<controllable::Controllable>();
+    ecs.register_serializable::<camera_followable::CameraFollowable>();
+    ecs.register_serializable::<mesh::MeshComponent>();
+    ecs.register_serializable::<static_collider::StaticCollider>();
+    ecs.register_serializable::<collider_shape::ColliderShape>();
+    ecs.register_serializable::<collision_layers::CollisionLayers>();
+    ecs.register_serializable::<tags::Player>();
+    ecs.register_serializable::<tags::Enemy>();
+    ecs.register_serializable::<tags::Static>();
+}
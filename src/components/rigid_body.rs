@@ -1,10 +1,21 @@
+use crate::{
+    constants::{DEFAULT_FRICTION, DEFAULT_MASS, DEFAULT_RESTITUTION, MIN_MASS},
+    ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter},
+    utils::{read_vec3, write_vec3},
+};
 use nalgebra_glm::Vec3;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct RigidBody {
     force: Vec3,
     velocity: Vec3,
     height: f32,
     radius: f32,
+    mass: f32,
+    /// Share of the speed a body keeps when it bounces off another, from 0 to 1.
+    restitution: f32,
+    /// How much sliding against another body slows it down, from 0 up.
+    friction: f32,
 }
 
 impl RigidBody {
@@ -14,6 +25,9 @@ impl RigidBody {
             velocity: Vec3::zeros(),
             height,
             radius,
+            mass: DEFAULT_MASS,
+            restitution: DEFAULT_RESTITUTION,
+            friction: DEFAULT_FRICTION,
         }
     }
 
@@ -23,9 +37,29 @@ impl RigidBody {
             velocity: Vec3::zeros(),
             height: 1.0,
             radius: 1.0,
+            mass: DEFAULT_MASS,
+            restitution: DEFAULT_RESTITUTION,
+            friction: DEFAULT_FRICTION,
         }
     }
 
+    /// Masses below `MIN_MASS`, zero and negative ones included, are raised to it. Giving a
+    /// body `f32::INFINITY` mass is the only way to make other bodies never move it.
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = mass.max(MIN_MASS);
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
     pub fn apply_force(&mut self, force: Vec3) {
         self.force += force;
     }
@@ -53,4 +87,120 @@ impl RigidBody {
     pub fn radius(&self) -> f32 {
         self.radius
     }
+
+    pub fn mass(&self) -> f32 {
+        self.mass
+    }
+
+    pub fn inverse_mass(&self) -> f32 {
+        1.0 / self.mass
+    }
+
+    pub fn restitution(&self) -> f32 {
+        self.restitution
+    }
+
+    pub fn friction(&self) -> f32 {
+        self.friction
+    }
+}
+
+impl Serializable for RigidBody {
+    const NAME: &'static str = "RigidBody";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        write_vec3(writer, self.force);
+        write_vec3(writer, self.velocity);
+        writer.f32(self.height);
+        writer.f32(self.radius);
+        writer.f32(self.mass);
+        writer.f32(self.restitution);
+        writer.f32(self.friction);
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        let body = Self {
+            force: read_vec3(reader)?,
+            velocity: read_vec3(reader)?,
+            height: reader.f32()?,
+            radius: reader.f32()?,
+            ..Self::default()
+        };
+        // Saves before version 2 had no material, those bodies keep the defaults.
+        if reader.version() < 2 {
+            return Ok(body);
+        }
+
+        Ok(body
+            .with_mass(reader.f32()?)
+            .with_restitution(reader.f32()?)
+            .with_friction(reader.f32()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::WorldFormat;
+
+    fn world() -> Ecs {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<RigidBody>()
+            .expect("Could not register component");
+        ecs.register_serializable::<RigidBody>();
+
+        ecs
+    }
+
+    fn loaded(ecs: &mut Ecs, save: &[u8]) -> RigidBody {
+        let entities = ecs.load_world(save).expect("Could not load world");
+
+        ecs.get_component_vec::<RigidBody>()
+            .expect("Could not get rigid bodies")
+            .get(entities[0].index())
+            .cloned()
+            .expect("Could not find rigid body")
+    }
+
+    #[test]
+    fn bodies_round_trip_with_their_material() {
+        let body = RigidBody::new(2.0, 0.5)
+            .with_mass(3.0)
+            .with_restitution(0.25)
+            .with_friction(0.75);
+
+        for format in [WorldFormat::Text, WorldFormat::Binary] {
+            let mut ecs = world();
+            let entity = ecs.create_entity().expect("Could not create entity");
+            ecs.add_component(entity, body.clone())
+                .expect("Could not add component");
+            let save = ecs.save_world(format).expect("Could not save world");
+
+            assert_eq!(loaded(&mut ecs, &save), body);
+        }
+    }
+
+    #[test]
+    fn version_1_saves_load_with_the_default_material() {
+        let save = b"goblin_world 1\nentity 0\nRigidBody 0.0 0.0 0.0 0.0 -1.0 0.0 2.0 0.5\n";
+        let mut expected = RigidBody::new(2.0, 0.5);
+        expected.set_velocity(Vec3::new(0.0, -1.0, 0.0));
+
+        assert_eq!(loaded(&mut world(), save), expected);
+    }
+
+    #[test]
+    fn masses_stay_positive() {
+        for mass in [0.0, -1.0, f32::NAN] {
+            let body = RigidBody::default().with_mass(mass);
+            assert_eq!(body.mass(), MIN_MASS);
+            assert!(body.inverse_mass().is_finite());
+        }
+        assert_eq!(
+            RigidBody::default().with_mass(f32::INFINITY).inverse_mass(),
+            0.0
+        );
+    }
 }
@@ -0,0 +1,128 @@
+use crate::{
+    collider::Collider,
+    components::{
+        collider_shape::ColliderShape, collision_layers::CollisionLayers,
+        global_transform::GlobalTransform, transform::Transform,
+    },
+    ecs::{
+        Changed, Component, Ecs, EcsError, Entity, Serializable, SerializationError, ValueReader,
+        ValueWriter, With,
+    },
+};
+use std::collections::HashSet;
+
+/// Makes the entity's `ColliderShape` fixed collision geometry at its `Transform`, on its
+/// `CollisionLayers`. Entities without a shape collide as a unit plane. Adding it registers the
+/// shape with the `Collider` resource, removing it or the entity unregisters it again.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StaticCollider;
+
+impl StaticCollider {
+    /// Keeps the `Collider` resource in sync with every `StaticCollider` in the world. The
+    /// collider is only updated once commands are applied, so the rest of a bundle, in
+    /// particular the `Transform` and `ColliderShape`, is in place by then.
+    pub fn register_hooks(ecs: &mut Ecs) {
+        ecs.on_add::<StaticCollider>(|ecs, entity| {
+            ecs.commands().add(move |ecs| {
+                if !ecs.is_alive(entity) {
+                    return Ok(());
+                }
+
+                let transform = *ecs
+                    .get_component_vec::<Transform>()?
+                    .get(entity.index())
+                    .ok_or(EcsError::MissingComponent)?;
+                let transform = GlobalTransform::world_matrix(ecs, entity, &transform)?;
+                let shape =
+                    optional::<ColliderShape>(ecs, entity)?.unwrap_or_else(ColliderShape::plane);
+                let layers = optional::<CollisionLayers>(ecs, entity)?.unwrap_or_default();
+                ecs.resource_mut::<Collider>()?
+                    .add_collidable(entity, &shape, layers, &transform);
+
+                Ok(())
+            });
+
+            Ok(())
+        });
+
+        ecs.on_remove::<StaticCollider>(|ecs, entity| {
+            ecs.commands().add(move |ecs| {
+                ecs.resource_mut::<Collider>()?.remove_collidable(entity);
+
+                Ok(())
+            });
+
+            Ok(())
+        });
+    }
+
+    /// Places the shapes of static colliders that moved, either by their own `Transform` or
+    /// along with a parent, or whose `ColliderShape` or `CollisionLayers` changed or went away,
+    /// again. Untouched ones are left alone. `last_run` is the change tick of the calling
+    /// system's previous run, changes after it count.
+    pub fn sync_moved(ecs: &Ecs, last_run: u32, collider: &mut Collider) -> Result<(), EcsError> {
+        let mut moved: HashSet<Entity> = changed::<Transform>(ecs, last_run)?;
+        moved.extend(changed::<GlobalTransform>(ecs, last_run)?);
+        moved.extend(changed::<ColliderShape>(ecs, last_run)?);
+        moved.extend(changed::<CollisionLayers>(ecs, last_run)?);
+        moved.extend(ecs.removed_components_since::<ColliderShape>(last_run));
+        moved.extend(ecs.removed_components_since::<CollisionLayers>(last_run));
+
+        let mut statics = ecs.query_filtered::<(
+            &Transform,
+            Option<&ColliderShape>,
+            Option<&CollisionLayers>,
+        ), With<StaticCollider>>()?;
+        for entity in moved {
+            let Ok((transform, shape, layers)) = statics.get(entity) else {
+                continue;
+            };
+            let shape = shape.cloned().unwrap_or_else(ColliderShape::plane);
+            let layers = layers.copied().unwrap_or_default();
+            let matrix = GlobalTransform::world_matrix(ecs, entity, transform)?;
+            collider.add_collidable(entity, &shape, layers, &matrix);
+        }
+
+        Ok(())
+    }
+}
+
+/// Static colliders whose `ComponentType` changed after `last_run`, none if the type isn't
+/// registered at all.
+fn changed<ComponentType: Component>(
+    ecs: &Ecs,
+    last_run: u32,
+) -> Result<HashSet<Entity>, EcsError> {
+    match ecs
+        .query_filtered_since::<&ComponentType, (With<StaticCollider>, Changed<ComponentType>)>(
+            last_run,
+        ) {
+        Ok(mut changed) => Ok(changed.iter().map(|(entity, _)| entity).collect()),
+        Err(EcsError::UnregisteredComponent) => Ok(HashSet::new()),
+        Err(error) => Err(error),
+    }
+}
+
+/// The entity's component, if the type is registered at all.
+fn optional<ComponentType: Component + Clone>(
+    ecs: &Ecs,
+    entity: Entity,
+) -> Result<Option<ComponentType>, EcsError> {
+    match ecs.get_component_vec::<ComponentType>() {
+        Ok(components) => Ok(components.get(entity.index()).cloned()),
+        Err(EcsError::UnregisteredComponent) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+impl Serializable for StaticCollider {
+    const NAME: &'static str = "StaticCollider";
+
+    fn save(&self, _ecs: &Ecs, _writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, _reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        Ok(StaticCollider)
+    }
+}
@@ -0,0 +1,38 @@
+use crate::ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter};
+
+/// Declares zero-sized marker components. They carry no data, so they are only useful as query
+/// filters such as `With<Player>`, and are saved as their bare name.
+macro_rules! tags {
+    ($($(#[$meta:meta])* $tag:ident),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+            pub struct $tag;
+
+            impl Serializable for $tag {
+                const NAME: &'static str = stringify!($tag);
+
+                fn save(
+                    &self,
+                    _ecs: &Ecs,
+                    _writer: &mut ValueWriter,
+                ) -> Result<(), SerializationError> {
+                    Ok(())
+                }
+
+                fn load(_ecs: &Ecs, _reader: &mut ValueReader) -> Result<Self, SerializationError> {
+                    Ok($tag)
+                }
+            }
+        )*
+    };
+}
+
+tags! {
+    /// The entity driven by the keyboard and mouse.
+    Player,
+    /// Anything hostile to the player.
+    Enemy,
+    /// Level geometry that never moves.
+    Static,
+}
@@ -1,6 +1,10 @@
+use crate::{
+    ecs::{Ecs, Serializable, SerializationError, ValueReader, ValueWriter},
+    utils::{read_vec3, write_vec3},
+};
 use nalgebra_glm::{Vec3, Vec4};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Transform {
     position: Vec3,
     rotation: Option<Vec4>,
@@ -32,3 +36,43 @@ impl Transform {
         self.scale
     }
 }
+
+impl Serializable for Transform {
+    const NAME: &'static str = "Transform";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        write_vec3(writer, self.position);
+        writer.bool(self.rotation.is_some());
+        if let Some(rotation) = self.rotation {
+            writer.f32(rotation.x);
+            writer.f32(rotation.y);
+            writer.f32(rotation.z);
+            writer.f32(rotation.w);
+        }
+        writer.bool(self.scale.is_some());
+        if let Some(scale) = self.scale {
+            write_vec3(writer, scale);
+        }
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        let position = read_vec3(reader)?;
+        let rotation = match reader.bool()? {
+            true => Some(Vec4::new(
+                reader.f32()?,
+                reader.f32()?,
+                reader.f32()?,
+                reader.f32()?,
+            )),
+            false => None,
+        };
+        let scale = match reader.bool()? {
+            true => Some(read_vec3(reader)?),
+            false => None,
+        };
+
+        Ok(Self::new(position, rotation, scale))
+    }
+}
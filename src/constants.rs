@@ -2,14 +2,42 @@ pub const SCREEN_WIDTH: f32 = 900.0;
 pub const SCREEN_HEIGHT: f32 = 700.0;
 pub const TICKS_PER_SECOND: f32 = 90.0;
 pub const TICK_RATE: f32 = 1000.0 / TICKS_PER_SECOND;
+pub const MAX_TICKS_PER_FRAME: u32 = 5;
 
 pub const CAMERA_FOV: f32 = 45.0;
 pub const PLAYER_MOVE_SPEED: f32 = 0.01;
 pub const MAX_PLAYER_VELOCITY: f32 = 7.0;
+pub const PLAYER_HEIGHT: f32 = 1.85;
+pub const PLAYER_RADIUS: f32 = 0.5;
 
 pub const GROUND_DRAG: f32 = 0.85;
+pub const GRAVITY: (f32, f32, f32) = (0.0, -0.001, 0.0);
+
+pub const DEFAULT_MASS: f32 = 1.0;
+/// Lightest a rigid body can be, so its inverse mass stays finite.
+pub const MIN_MASS: f32 = 0.001;
+pub const DEFAULT_RESTITUTION: f32 = 0.0;
+pub const DEFAULT_FRICTION: f32 = 0.5;
 
 pub const COLLISION_RANGE: f32 = 0.1;
+/// How many overlaps a body is pushed out of per tick.
+pub const CONTACT_ITERATIONS: usize = 4;
+/// Contacts whose normal points at least this much upwards count as standing on the ground.
+pub const GROUND_NORMAL_Y: f32 = 0.7;
+/// Passes over the contacts between bodies per tick, more passes settle stacks better.
+pub const BODY_SOLVER_ITERATIONS: usize = 8;
+/// Overlap between bodies that is left alone, so resting bodies don't jitter.
+pub const PENETRATION_SLOP: f32 = 0.005;
+/// Share of the remaining overlap between bodies pushed apart per tick.
+pub const POSITION_CORRECTION: f32 = 0.8;
+/// Bodies meeting slower than this rest against each other instead of bouncing or landing.
+pub const RESTING_SPEED: f32 = 0.005;
+/// Halvings that narrow down where a shape cast first touches something.
+pub const SHAPE_CAST_REFINE_STEPS: usize = 24;
+/// Width of the broadphase grid cells, a little above the size of most props.
+pub const BROADPHASE_CELL_SIZE: f32 = 4.0;
+/// Collidables covering more cells than this, such as floors, skip the grid.
+pub const MAX_CELLS_PER_ENTRY: usize = 64;
 
 pub const MOUSE_SENSITIVITY: f32 = 0.1;
 
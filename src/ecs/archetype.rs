@@ -0,0 +1,76 @@
+use std::{any::TypeId, collections::HashMap};
+
+pub type ArchetypeId = usize;
+
+/// Archetype of entities without any table stored components.
+pub const EMPTY_ARCHETYPE: ArchetypeId = 0;
+
+/// Tracks which set of table stored components every entity has. Entities with the same set
+/// share an archetype and therefore a column in each of those components' tables.
+pub struct Archetypes {
+    components: Vec<Vec<TypeId>>,
+    ids: HashMap<Vec<TypeId>, ArchetypeId>,
+    entities: Vec<ArchetypeId>,
+}
+
+impl Archetypes {
+    pub fn new() -> Self {
+        Self {
+            components: vec![Vec::new()],
+            ids: HashMap::from([(Vec::new(), EMPTY_ARCHETYPE)]),
+            entities: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> ArchetypeId {
+        self.entities.get(index).copied().unwrap_or(EMPTY_ARCHETYPE)
+    }
+
+    pub fn set(&mut self, index: usize, archetype: ArchetypeId) {
+        if index >= self.entities.len() {
+            self.entities.resize(index + 1, EMPTY_ARCHETYPE);
+        }
+        self.entities[index] = archetype;
+    }
+
+    /// Sorted component types of the archetype.
+    pub fn components(&self, archetype: ArchetypeId) -> &[TypeId] {
+        &self.components[archetype]
+    }
+
+    /// The archetype holding the same components as `archetype` plus `type_id`.
+    pub fn with(&mut self, archetype: ArchetypeId, type_id: TypeId) -> ArchetypeId {
+        let mut components = self.components[archetype].clone();
+        if let Err(position) = components.binary_search(&type_id) {
+            components.insert(position, type_id);
+        }
+
+        self.get_or_insert(components)
+    }
+
+    /// The archetype holding the same components as `archetype` except `type_id`.
+    pub fn without(&mut self, archetype: ArchetypeId, type_id: TypeId) -> ArchetypeId {
+        let mut components = self.components[archetype].clone();
+        components.retain(|other| *other != type_id);
+
+        self.get_or_insert(components)
+    }
+
+    fn get_or_insert(&mut self, components: Vec<TypeId>) -> ArchetypeId {
+        if let Some(&archetype) = self.ids.get(&components) {
+            return archetype;
+        }
+
+        let archetype = self.components.len();
+        self.components.push(components.clone());
+        self.ids.insert(components, archetype);
+
+        archetype
+    }
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,60 @@
+use super::{Component, Ecs, EcsError, Entity};
+
+/// A group of components inserted together, either a tuple such as `(Transform, MeshComponent)`
+/// or a named type describing a common kind of entity.
+pub trait Bundle: Send + 'static {
+    fn insert(self, ecs: &mut Ecs, entity: Entity) -> Result<(), EcsError>;
+}
+
+impl Bundle for () {
+    fn insert(self, _ecs: &mut Ecs, _entity: Entity) -> Result<(), EcsError> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_bundle_tuple {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Component),+> Bundle for ($($name,)+) {
+            fn insert(self, ecs: &mut Ecs, entity: Entity) -> Result<(), EcsError> {
+                let ($($name,)+) = self;
+                $(ecs.add_component(entity, $name)?;)+
+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_bundle_tuple!(A);
+impl_bundle_tuple!(A, B);
+impl_bundle_tuple!(A, B, C);
+impl_bundle_tuple!(A, B, C, D);
+impl_bundle_tuple!(A, B, C, D, E);
+impl_bundle_tuple!(A, B, C, D, E, F);
+impl_bundle_tuple!(A, B, C, D, E, F, G);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+impl Ecs {
+    /// Creates an entity holding every component of the bundle. If any of them can't be
+    /// added, the entity is removed again and the error returned.
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> Result<Entity, EcsError> {
+        let entity = self.create_entity()?;
+        if let Err(error) = bundle.insert(self, entity) {
+            self.remove_entity(entity)?;
+            return Err(error);
+        }
+
+        Ok(entity)
+    }
+
+    /// Adds every component of the bundle to an existing entity, replacing ones it already has.
+    pub fn insert_bundle<B: Bundle>(&mut self, entity: Entity, bundle: B) -> Result<(), EcsError> {
+        self.validate(entity)?;
+        bundle.insert(self, entity)
+    }
+}
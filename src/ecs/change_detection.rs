@@ -0,0 +1,186 @@
+use super::{query::QueryFilter, read_lock, storage::ComponentStorage, Component, Ecs, EcsError};
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::RwLockReadGuard,
+};
+
+/// How many ticks the world may advance between checks for change ticks that are getting old.
+pub const CHECK_TICK_THRESHOLD: u32 = 518_400_000;
+
+/// Oldest a change tick can get before it is clamped, leaving room for two checks so that ticks
+/// never get old enough to wrap around and look new again.
+pub const MAX_CHANGE_AGE: u32 = u32::MAX - (2 * CHECK_TICK_THRESHOLD - 1);
+
+/// Whether `tick` is after `last_run`. Both are compared by how long before `this_run` they
+/// were, so the `u32` ticks keep comparing correctly after wrapping around.
+pub fn is_newer(tick: u32, last_run: u32, this_run: u32) -> bool {
+    this_run.wrapping_sub(last_run) > this_run.wrapping_sub(tick)
+}
+
+/// Moves `tick` forward to `MAX_CHANGE_AGE` before `this_run` if it is any older.
+pub fn clamp_tick(tick: &mut u32, this_run: u32) {
+    if this_run.wrapping_sub(*tick) > MAX_CHANGE_AGE {
+        *tick = this_run.wrapping_sub(MAX_CHANGE_AGE);
+    }
+}
+
+/// The ticks a component was added and last changed at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentTicks {
+    added: u32,
+    changed: u32,
+}
+
+impl ComponentTicks {
+    pub fn new(change_tick: u32) -> Self {
+        Self {
+            added: change_tick,
+            changed: change_tick,
+        }
+    }
+
+    /// Whether the component was added after `last_run`, as seen at `this_run`.
+    pub fn is_added(&self, last_run: u32, this_run: u32) -> bool {
+        is_newer(self.added, last_run, this_run)
+    }
+
+    /// Whether the component was added or changed after `last_run`, as seen at `this_run`.
+    pub fn is_changed(&self, last_run: u32, this_run: u32) -> bool {
+        is_newer(self.changed, last_run, this_run)
+    }
+
+    /// Clamps both ticks to at most `MAX_CHANGE_AGE` before `this_run`.
+    pub fn clamp(&mut self, this_run: u32) {
+        clamp_tick(&mut self.added, this_run);
+        clamp_tick(&mut self.changed, this_run);
+    }
+
+    pub fn set_changed(&mut self, change_tick: u32) {
+        self.changed = change_tick;
+    }
+}
+
+/// Mutable access to a component that flags it as changed the first time it is written through.
+/// Reading through a `Mut` leaves the change ticks alone.
+pub struct Mut<'a, T> {
+    component: &'a mut T,
+    ticks: &'a mut ComponentTicks,
+    change_tick: u32,
+}
+
+impl<'a, T> Mut<'a, T> {
+    pub(super) fn new(
+        component: &'a mut T,
+        ticks: &'a mut ComponentTicks,
+        change_tick: u32,
+    ) -> Self {
+        Self {
+            component,
+            ticks,
+            change_tick,
+        }
+    }
+
+    pub fn ticks(&self) -> ComponentTicks {
+        *self.ticks
+    }
+
+    /// Consumes the wrapper, flagging the component as changed.
+    pub fn into_inner(self) -> &'a mut T {
+        self.ticks.set_changed(self.change_tick);
+        self.component
+    }
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.component
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.ticks.set_changed(self.change_tick);
+        self.component
+    }
+}
+
+/// Only matches entities whose `T` component was added after the tick the query was made for:
+/// the system's last run in [`SystemContext::query_filtered`], or the last `clear_trackers` in
+/// [`Ecs::query_filtered`].
+///
+/// [`SystemContext::query_filtered`]: crate::systems::SystemContext::query_filtered
+///
+/// Borrows the `T` vector for reading, so it can't be combined with `&mut T` in one query.
+pub struct Added<T>(PhantomData<T>);
+
+/// Only matches entities whose `T` component was added or written to after the tick the query was
+/// made for, see [`Added`].
+///
+/// Borrows the `T` vector for reading, so it can't be combined with `&mut T` in one query.
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: Component> QueryFilter for Added<T> {
+    type State<'w> = (RwLockReadGuard<'w, ComponentStorage<T>>, u32, u32);
+
+    fn borrow_state(ecs: &Ecs, last_run: u32) -> Result<Self::State<'_>, EcsError> {
+        Ok((
+            read_lock(ecs.component_cell::<T>()?)?,
+            last_run,
+            ecs.change_tick(),
+        ))
+    }
+
+    fn matches((storage, last_run, this_run): &Self::State<'_>, index: usize) -> bool {
+        storage
+            .ticks(index)
+            .is_some_and(|ticks| ticks.is_added(*last_run, *this_run))
+    }
+}
+
+impl<T: Component> QueryFilter for Changed<T> {
+    type State<'w> = (RwLockReadGuard<'w, ComponentStorage<T>>, u32, u32);
+
+    fn borrow_state(ecs: &Ecs, last_run: u32) -> Result<Self::State<'_>, EcsError> {
+        Ok((
+            read_lock(ecs.component_cell::<T>()?)?,
+            last_run,
+            ecs.change_tick(),
+        ))
+    }
+
+    fn matches((storage, last_run, this_run): &Self::State<'_>, index: usize) -> bool {
+        storage
+            .ticks(index)
+            .is_some_and(|ticks| ticks.is_changed(*last_run, *this_run))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_compare_across_wraparound() {
+        let last_run = u32::MAX - 1;
+        let this_run = 3;
+
+        assert!(is_newer(u32::MAX, last_run, this_run));
+        assert!(is_newer(1, last_run, this_run));
+        assert!(!is_newer(u32::MAX - 5, last_run, this_run));
+        assert!(!is_newer(last_run, last_run, this_run));
+    }
+
+    #[test]
+    fn old_ticks_are_clamped_before_they_look_new() {
+        let mut ticks = ComponentTicks::new(5);
+        let this_run = 5u32.wrapping_add(MAX_CHANGE_AGE).wrapping_add(10);
+        ticks.clamp(this_run);
+
+        assert_eq!(this_run.wrapping_sub(ticks.added), MAX_CHANGE_AGE);
+        assert!(!ticks.is_changed(this_run - 1, this_run));
+    }
+}
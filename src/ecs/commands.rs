@@ -0,0 +1,416 @@
+use super::{Bundle, Component, Ecs, EcsError, Entity};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::MutexGuard,
+    thread::{self, ThreadId},
+};
+
+type Insert = Box<dyn FnOnce(&mut Ecs, Entity) -> Result<(), EcsError> + Send>;
+type Custom = Box<dyn FnOnce(&mut Ecs) -> Result<(), EcsError> + Send>;
+
+enum Command {
+    Spawn(Entity, Vec<Insert>),
+    Custom(Custom),
+}
+
+/// Structural changes recorded while the world is borrowed, e.g. from inside
+/// `System::run`. Nothing happens until [`Ecs::apply_commands`] runs them in the order they
+/// were queued.
+pub struct Commands {
+    queue: Vec<(ThreadId, Command)>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self { queue: Vec::new() }
+    }
+
+    /// Queues components for an entity reserved through [`Ecs::reserve_entity`]. Usually
+    /// called through [`LockedCommands::spawn`], which reserves the entity as well.
+    pub fn spawn_reserved(&mut self, entity: Entity) -> EntityCommands<'_> {
+        self.queue
+            .push((thread::current().id(), Command::Spawn(entity, Vec::new())));
+        let index = self.queue.len() - 1;
+
+        EntityCommands {
+            commands: self,
+            index,
+            entity,
+        }
+    }
+
+    pub fn insert<ComponentType: Component>(&mut self, entity: Entity, component: ComponentType) {
+        self.add(move |ecs| ecs.add_component(entity, component));
+    }
+
+    pub fn insert_bundle<B: Bundle>(&mut self, entity: Entity, bundle: B) {
+        self.add(move |ecs| ecs.insert_bundle(entity, bundle));
+    }
+
+    pub fn remove<ComponentType: Component>(&mut self, entity: Entity) {
+        self.add(move |ecs| ecs.remove_component::<ComponentType>(entity));
+    }
+
+    /// Queues removal of the entity and everything attached below it.
+    pub fn despawn(&mut self, entity: Entity) {
+        self.add(move |ecs| ecs.remove_entity(entity));
+    }
+
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.add(move |ecs| ecs.set_parent(child, parent));
+    }
+
+    pub fn remove_parent(&mut self, child: Entity) {
+        self.add(move |ecs| ecs.remove_parent(child));
+    }
+
+    /// Queues an arbitrary change to the world.
+    pub fn add(&mut self, command: impl FnOnce(&mut Ecs) -> Result<(), EcsError> + Send + 'static) {
+        self.queue
+            .push((thread::current().id(), Command::Custom(Box::new(command))));
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub(super) fn sort_by_thread(&mut self, threads: &[ThreadId]) {
+        self.queue.sort_by_key(|(thread, _)| {
+            threads
+                .iter()
+                .position(|other| other == thread)
+                .unwrap_or(threads.len())
+        });
+    }
+
+    /// Applies every queued command. A failing command does not stop the ones after it, the
+    /// first error is returned once the queue is drained.
+    pub(super) fn apply(self, ecs: &mut Ecs) -> Result<(), EcsError> {
+        let mut result = Ok(());
+
+        for (_, command) in self.queue {
+            let applied = match command {
+                Command::Spawn(entity, inserts) => ecs.validate(entity).and_then(|()| {
+                    // Like `Ecs::spawn`, an entity missing some of its components is removed.
+                    let inserted = inserts
+                        .into_iter()
+                        .try_for_each(|insert| insert(ecs, entity));
+                    if inserted.is_err() {
+                        ecs.remove_entity(entity)?;
+                    }
+                    inserted
+                }),
+                Command::Custom(command) => command(ecs),
+            };
+
+            if result.is_ok() {
+                result = applied;
+            }
+        }
+
+        result
+    }
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The world's [`Commands`], locked by [`Ecs::commands`]. Spawning through it reserves the
+/// entity right away, so later commands can refer to it.
+pub struct LockedCommands<'a> {
+    ecs: &'a Ecs,
+    commands: MutexGuard<'a, Commands>,
+}
+
+impl<'a> LockedCommands<'a> {
+    pub(super) fn new(ecs: &'a Ecs, commands: MutexGuard<'a, Commands>) -> Self {
+        Self { ecs, commands }
+    }
+
+    /// Queues a new entity. Components added through the returned builder are inserted once
+    /// commands are applied, and if any of them fails the entity is removed again. Fails if
+    /// the entity limit leaves no room for it.
+    pub fn spawn(&mut self) -> Result<EntityCommands<'_>, EcsError> {
+        let entity = self.ecs.reserve_entity()?;
+
+        Ok(self.commands.spawn_reserved(entity))
+    }
+}
+
+impl Deref for LockedCommands<'_> {
+    type Target = Commands;
+
+    fn deref(&self) -> &Commands {
+        &self.commands
+    }
+}
+
+impl DerefMut for LockedCommands<'_> {
+    fn deref_mut(&mut self) -> &mut Commands {
+        &mut self.commands
+    }
+}
+
+pub struct EntityCommands<'a> {
+    commands: &'a mut Commands,
+    index: usize,
+    entity: Entity,
+}
+
+impl<'a> EntityCommands<'a> {
+    /// The entity being spawned, already usable in other commands.
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn insert<ComponentType: Component>(self, component: ComponentType) -> Self {
+        if let (_, Command::Spawn(_, inserts)) = &mut self.commands.queue[self.index] {
+            inserts.push(Box::new(move |ecs, entity| {
+                ecs.add_component(entity, component)
+            }));
+        }
+
+        self
+    }
+
+    pub fn insert_bundle<B: Bundle>(self, bundle: B) -> Self {
+        if let (_, Command::Spawn(_, inserts)) = &mut self.commands.queue[self.index] {
+            inserts.push(Box::new(move |ecs, entity| bundle.insert(ecs, entity)));
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::Parent,
+        systems::{System, SystemContext, SystemError},
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    struct Target;
+
+    struct Projectile;
+
+    /// Never registered.
+    struct Unknown;
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    fn world() -> Ecs {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<Health>()
+            .expect("Could not register component");
+        ecs.register_component::<Target>()
+            .expect("Could not register component");
+        ecs.register_component::<Projectile>()
+            .expect("Could not register component");
+        ecs.insert_resource(Log::default());
+
+        ecs
+    }
+
+    fn health(ecs: &Ecs, entity: Entity) -> Option<u32> {
+        ecs.get_component_vec::<Health>()
+            .expect("Could not get health")
+            .get(entity.index())
+            .map(|health| health.0)
+    }
+
+    fn parent(ecs: &Ecs, entity: Entity) -> Option<Entity> {
+        ecs.get_component_vec::<Parent>()
+            .expect("Could not get parents")
+            .get(entity.index())
+            .map(Parent::get)
+    }
+
+    #[test]
+    fn commands_apply_in_queue_order() {
+        let mut ecs = world();
+        let entity = ecs.create_entity().expect("Could not create entity");
+
+        let mut commands = ecs.commands();
+        commands.insert(entity, Health(1));
+        commands.add(move |ecs| {
+            let line = format!("{:?}", health(ecs, entity));
+            ecs.resource_mut::<Log>()?.0.push(line);
+            Ok(())
+        });
+        commands.remove::<Health>(entity);
+        commands.add(move |ecs| {
+            let line = format!("{:?}", health(ecs, entity));
+            ecs.resource_mut::<Log>()?.0.push(line);
+            Ok(())
+        });
+        drop(commands);
+        ecs.apply_commands().expect("Could not apply commands");
+
+        let log = ecs.resource::<Log>().expect("Could not get log");
+        assert_eq!(log.0, ["Some(1)", "None"]);
+    }
+
+    #[test]
+    fn failing_commands_do_not_stop_the_rest() {
+        let mut ecs = world();
+        let removed = ecs.create_entity().expect("Could not create entity");
+        let kept = ecs.create_entity().expect("Could not create entity");
+        ecs.remove_entity(removed).expect("Could not remove entity");
+
+        let mut commands = ecs.commands();
+        commands.insert(removed, Health(1));
+        commands.insert(kept, Unknown);
+        commands.insert(kept, Health(2));
+        drop(commands);
+
+        // The first error is returned once every command ran.
+        assert!(matches!(ecs.apply_commands(), Err(EcsError::StaleEntity)));
+        assert_eq!(health(&ecs, kept), Some(2));
+        assert!(ecs.commands().is_empty());
+        ecs.apply_commands().expect("Could not apply commands");
+    }
+
+    #[test]
+    fn spawned_entities_can_be_referred_to_before_they_exist() {
+        let mut ecs = world();
+        let freed = ecs.create_entity().expect("Could not create entity");
+        ecs.remove_entity(freed).expect("Could not remove entity");
+
+        let mut commands = ecs.commands();
+        let parent_id = commands
+            .spawn()
+            .expect("Could not spawn entity")
+            .insert(Health(1))
+            .id();
+        let child = commands
+            .spawn()
+            .expect("Could not spawn entity")
+            .insert(Health(2))
+            .id();
+        commands.set_parent(child, parent_id);
+        drop(commands);
+
+        // The freed slot is reused first, with a new generation.
+        assert_eq!(parent_id.index(), freed.index());
+        assert_ne!(parent_id, freed);
+        assert!(!ecs.is_alive(parent_id) && !ecs.is_alive(child));
+
+        ecs.apply_commands().expect("Could not apply commands");
+        assert_eq!(health(&ecs, parent_id), Some(1));
+        assert_eq!(health(&ecs, child), Some(2));
+        assert_eq!(parent(&ecs, child), Some(parent_id));
+    }
+
+    #[test]
+    fn reserved_entities_are_not_handed_out_twice() {
+        let mut ecs = world();
+        let reserved = ecs
+            .commands()
+            .spawn()
+            .expect("Could not spawn entity")
+            .insert(Health(1))
+            .id();
+
+        let created = ecs.create_entity().expect("Could not create entity");
+        assert_ne!(created, reserved);
+
+        ecs.apply_commands().expect("Could not apply commands");
+        assert_eq!(health(&ecs, reserved), Some(1));
+        assert_eq!(health(&ecs, created), None);
+        assert_eq!(ecs.entity_count(), 2);
+    }
+
+    #[test]
+    fn spawns_missing_a_component_are_undone() {
+        let mut ecs = world();
+        let spawned = ecs
+            .commands()
+            .spawn()
+            .expect("Could not spawn entity")
+            .insert(Health(1))
+            .insert(Unknown)
+            .id();
+
+        assert!(matches!(
+            ecs.apply_commands(),
+            Err(EcsError::UnregisteredComponent)
+        ));
+        assert!(!ecs.is_alive(spawned));
+        assert_eq!(health(&ecs, spawned), None);
+        assert_eq!(ecs.entity_count(), 0);
+    }
+
+    #[test]
+    fn spawns_past_the_entity_limit_fail_right_away() {
+        let mut ecs = world();
+        ecs.set_entity_limit(Some(1));
+        ecs.commands().spawn().expect("Could not spawn entity");
+
+        assert!(matches!(
+            ecs.commands().spawn().map(|spawned| spawned.id()),
+            Err(EcsError::TooManyEntities)
+        ));
+        ecs.apply_commands().expect("Could not apply commands");
+        assert_eq!(ecs.entity_count(), 1);
+    }
+
+    /// Fires a projectile from `launcher` at every target, which is destroyed.
+    struct Launcher(Entity);
+
+    impl System for Launcher {
+        fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+            let ecs = context.ecs;
+            let mut targets = ecs
+                .query::<&Target>()
+                .map_err(|_| SystemError::ComponentError)?;
+            let mut commands = ecs.commands();
+            for (target, _) in targets.iter() {
+                let projectile = commands
+                    .spawn()
+                    .map_err(|_| SystemError::EntityError)?
+                    .insert(Projectile)
+                    .id();
+                commands.set_parent(projectile, self.0);
+                commands.despawn(target);
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn systems_spawn_and_despawn_while_running() {
+        let mut ecs = world();
+        let launcher = ecs.create_entity().expect("Could not create entity");
+        let targets: Vec<Entity> = (0..3)
+            .map(|_| ecs.spawn((Target,)).expect("Could not spawn target"))
+            .collect();
+
+        Launcher(launcher)
+            .run(SystemContext::new(&ecs, 0))
+            .expect("Could not run launcher");
+        ecs.apply_commands().expect("Could not apply commands");
+
+        assert!(targets.iter().all(|target| !ecs.is_alive(*target)));
+        let mut projectiles = ecs
+            .query::<&Projectile>()
+            .expect("Could not query projectiles");
+        let fired: Vec<Entity> = projectiles.iter().map(|(entity, _)| entity).collect();
+        assert_eq!(fired.len(), 3);
+        assert!(fired
+            .iter()
+            .all(|projectile| parent(&ecs, *projectile) == Some(launcher)));
+    }
+}
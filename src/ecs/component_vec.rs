@@ -1,13 +1,23 @@
-use std::cell::RefCell;
+use super::{
+    archetype::ArchetypeId, memory::ComponentMemory, read_lock, storage::ComponentStorage,
+    Component, EcsError,
+};
+use std::sync::{PoisonError, RwLock};
 
-pub trait ComponentVec {
+pub trait ComponentVec: Send + Sync {
     fn as_any(&self) -> &dyn std::any::Any;
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+    fn memory_usage(&self) -> Result<ComponentMemory, EcsError>;
     fn push_none(&mut self);
-    fn remove(&mut self, index: usize);
+    fn move_to(&mut self, index: usize, archetype: ArchetypeId);
+    fn contains(&self, index: usize) -> bool;
+    /// Removes the entity's component, returning whether it had one.
+    fn clear(&mut self, index: usize) -> bool;
+    /// Clamps change ticks older than `MAX_CHANGE_AGE` before `change_tick`.
+    fn clamp_ticks(&mut self, change_tick: u32);
 }
 
-impl<T: 'static> ComponentVec for RefCell<Vec<Option<T>>> {
+impl<T: Component> ComponentVec for RwLock<ComponentStorage<T>> {
     fn as_any(&self) -> &dyn std::any::Any {
         self as &dyn std::any::Any
     }
@@ -16,23 +26,44 @@ impl<T: 'static> ComponentVec for RefCell<Vec<Option<T>>> {
         self as &mut dyn std::any::Any
     }
 
+    fn memory_usage(&self) -> Result<ComponentMemory, EcsError> {
+        let storage = read_lock(self)?;
+
+        Ok(ComponentMemory {
+            name: std::any::type_name::<T>(),
+            storage_type: storage.storage_type(),
+            bytes: storage.memory_usage(),
+        })
+    }
+
     fn push_none(&mut self) {
-        self.get_mut().push(None);
+        if let ComponentStorage::Dense(components) =
+            self.get_mut().unwrap_or_else(PoisonError::into_inner)
+        {
+            components.push(None);
+        }
     }
 
-    fn remove(&mut self, index: usize) {
-        let vec = self.get_mut();
+    fn move_to(&mut self, index: usize, archetype: ArchetypeId) {
+        self.get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .move_to(index, archetype);
+    }
 
-        if index >= vec.len() {
-            return;
-        }
+    fn contains(&self, index: usize) -> bool {
+        read_lock(self).is_ok_and(|storage| storage.contains(index))
+    }
 
-        if index == vec.len() - 1 {
-            vec.pop();
-            return;
-        }
+    fn clear(&mut self, index: usize) -> bool {
+        self.get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(index)
+            .is_some()
+    }
 
-        let last_element = vec.pop().expect("Tried to pop element from empty vector");
-        vec[index] = last_element;
+    fn clamp_ticks(&mut self, change_tick: u32) {
+        self.get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clamp_ticks(change_tick);
     }
 }
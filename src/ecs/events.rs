@@ -0,0 +1,193 @@
+use super::{Ecs, EcsError};
+use std::{marker::PhantomData, sync::RwLockWriteGuard};
+
+/// Anything that can be sent between systems through an [`Events`] queue.
+pub trait Event: Send + Sync + 'static {}
+
+impl<T: Send + Sync + 'static> Event for T {}
+
+/// Double-buffered queue of `T` events, stored in the world as a resource. Each call to
+/// [`Events::update`] drops the older buffer, so an event stays readable for the update it was
+/// sent in and the one after.
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    previous_start: usize,
+    event_count: usize,
+}
+
+impl<T: Event> Events<T> {
+    pub fn new() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            previous_start: 0,
+            event_count: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+        self.event_count += 1;
+    }
+
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        self.previous_start = self.event_count - self.previous.len();
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(super) fn update_system(ecs: &Ecs) {
+        if let Ok(mut events) = ecs.resource_mut::<Events<T>>() {
+            events.update();
+        }
+    }
+}
+
+impl<T: Event> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sends `T` events while holding the queue borrowed.
+pub struct EventWriter<'a, T: Event> {
+    events: RwLockWriteGuard<'a, Events<T>>,
+}
+
+impl<'a, T: Event> EventWriter<'a, T> {
+    pub(super) fn new(ecs: &'a Ecs) -> Result<Self, EcsError> {
+        Ok(Self {
+            events: ecs.resource_mut::<Events<T>>()?,
+        })
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+}
+
+/// Remembers how far into an [`Events`] queue its owner has read, so every reader sees each
+/// event once no matter how many other readers there are.
+pub struct EventReader<T: Event> {
+    last_event_count: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Event> EventReader<T> {
+    pub fn new() -> Self {
+        Self {
+            last_event_count: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Events sent since the last call. Events older than the previous update are gone and
+    /// silently skipped.
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let last_event_count = self.last_event_count;
+        let unread = move |start: usize, buffer: &'a Vec<T>| {
+            buffer.iter().skip(last_event_count.saturating_sub(start))
+        };
+        let current_start = events.previous_start + events.previous.len();
+        let unread = unread(events.previous_start, &events.previous)
+            .chain(unread(current_start, &events.current));
+
+        self.last_event_count = events.event_count;
+
+        unread
+    }
+}
+
+impl<T: Event> Default for EventReader<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(reader: &mut EventReader<u32>, events: &Events<u32>) -> Vec<u32> {
+        reader.read(events).copied().collect()
+    }
+
+    #[test]
+    fn events_survive_exactly_one_update() {
+        let mut events = Events::new();
+        events.send(1);
+
+        events.update();
+        assert_eq!(events.len(), 1);
+        assert_eq!(read(&mut EventReader::new(), &events), [1]);
+
+        events.update();
+        assert!(events.is_empty());
+        assert_eq!(read(&mut EventReader::new(), &events), []);
+    }
+
+    #[test]
+    fn every_reader_sees_every_event_once() {
+        let mut events = Events::new();
+        let mut first = EventReader::new();
+        let mut second = EventReader::new();
+
+        events.send(1);
+        events.send(2);
+        assert_eq!(read(&mut first, &events), [1, 2]);
+
+        events.send(3);
+        events.update();
+        assert_eq!(read(&mut first, &events), [3]);
+        assert_eq!(read(&mut second, &events), [1, 2, 3]);
+
+        events.send(4);
+        assert_eq!(read(&mut first, &events), [4]);
+        assert_eq!(read(&mut second, &events), [4]);
+        assert_eq!(read(&mut first, &events), []);
+        assert_eq!(read(&mut second, &events), []);
+    }
+
+    #[test]
+    fn readers_falling_behind_skip_dropped_events() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+
+        events.send(1);
+        events.update();
+        events.send(2);
+        events.update();
+        events.send(3);
+
+        assert_eq!(read(&mut reader, &events), [2, 3]);
+    }
+
+    #[test]
+    fn the_world_updates_registered_events() {
+        let mut ecs = Ecs::new();
+        ecs.add_event::<u32>();
+        ecs.event_writer::<u32>()
+            .expect("Could not get writer")
+            .send(1);
+        let mut reader = EventReader::new();
+
+        ecs.update_events();
+        let events = ecs.events::<u32>().expect("Could not get events");
+        assert_eq!(read(&mut reader, &events), [1]);
+        drop(events);
+
+        ecs.update_events();
+        assert!(ecs
+            .events::<u32>()
+            .expect("Could not get events")
+            .is_empty());
+    }
+}
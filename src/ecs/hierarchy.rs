@@ -0,0 +1,105 @@
+use super::{Ecs, EcsError, Entity};
+
+/// The entity this one is attached to. Set through [`Ecs::set_parent`] so the parent's
+/// [`Children`] stay in sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parent(Entity);
+
+impl Parent {
+    pub(super) fn new(parent: Entity) -> Self {
+        Self(parent)
+    }
+
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Entities attached to this one, in the order they were attached.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub(super) fn new(children: Vec<Entity>) -> Self {
+        Self(children)
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Ecs {
+    /// Attaches `child` to `parent`, detaching it from any previous parent first.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), EcsError> {
+        self.validate(child)?;
+        self.validate(parent)?;
+        if self.is_ancestor(child, parent) {
+            return Err(EcsError::HierarchyCycle);
+        }
+
+        self.remove_parent(child)?;
+        self.add_component(child, Parent(parent))?;
+        match self.get_component::<Children>(parent)? {
+            Some(children) => children.0.push(child),
+            None => self.add_component(parent, Children(vec![child]))?,
+        }
+
+        Ok(())
+    }
+
+    /// Detaches `child` from its parent, making it a root. Does nothing if it has no parent.
+    pub fn remove_parent(&mut self, child: Entity) -> Result<(), EcsError> {
+        self.validate(child)?;
+        let parent = self
+            .component_vec_mut::<Parent>()?
+            .get(child.index)
+            .copied();
+        let Some(Parent(parent)) = parent else {
+            return Ok(());
+        };
+
+        self.remove_component::<Parent>(child)?;
+        if !self.is_alive(parent) {
+            return Ok(());
+        }
+        if let Some(children) = self.get_component::<Children>(parent)? {
+            children.0.retain(|other| *other != child);
+        }
+
+        Ok(())
+    }
+
+    /// Whether `ancestor` is `entity` itself or any entity above it in the hierarchy.
+    fn is_ancestor(&mut self, ancestor: Entity, entity: Entity) -> bool {
+        let Ok(parents) = self.component_vec_mut::<Parent>() else {
+            return false;
+        };
+
+        let mut current = Some(entity);
+        while let Some(entity) = current {
+            if entity == ancestor {
+                return true;
+            }
+            current = parents.get(entity.index).map(Parent::get);
+        }
+
+        false
+    }
+
+    pub(super) fn take_children(&mut self, entity: Entity) -> Vec<Entity> {
+        self.component_vec_mut::<Children>()
+            .ok()
+            .and_then(|children| children.get_mut(entity.index))
+            .map(|children| std::mem::take(&mut children.0))
+            .unwrap_or_default()
+    }
+}
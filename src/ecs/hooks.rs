@@ -0,0 +1,93 @@
+use super::{Component, Ecs, EcsError, Entity};
+use std::any::TypeId;
+
+/// Callback run when a component changes on an entity. Hooks only get shared access to the
+/// world; structural changes have to be queued through [`Ecs::commands`].
+pub type ComponentHook = Box<dyn Fn(&Ecs, Entity) -> Result<(), EcsError> + Send + Sync>;
+
+#[derive(Default)]
+pub(super) struct ComponentHooks {
+    on_add: Vec<ComponentHook>,
+    on_replace: Vec<ComponentHook>,
+    on_remove: Vec<ComponentHook>,
+}
+
+/// The point in a component's life a hook runs at.
+#[derive(Clone, Copy)]
+pub(super) enum HookKind {
+    Add,
+    Replace,
+    Remove,
+}
+
+impl ComponentHooks {
+    fn get(&self, kind: HookKind) -> &[ComponentHook] {
+        match kind {
+            HookKind::Add => &self.on_add,
+            HookKind::Replace => &self.on_replace,
+            HookKind::Remove => &self.on_remove,
+        }
+    }
+}
+
+impl Ecs {
+    /// Runs `hook` right after a `ComponentType` is added to an entity that did not have one.
+    pub fn on_add<ComponentType: Component>(
+        &mut self,
+        hook: impl Fn(&Ecs, Entity) -> Result<(), EcsError> + Send + Sync + 'static,
+    ) {
+        self.component_hooks::<ComponentType>()
+            .on_add
+            .push(Box::new(hook));
+    }
+
+    /// Runs `hook` right after an entity's `ComponentType` is overwritten by `add_component`.
+    pub fn on_replace<ComponentType: Component>(
+        &mut self,
+        hook: impl Fn(&Ecs, Entity) -> Result<(), EcsError> + Send + Sync + 'static,
+    ) {
+        self.component_hooks::<ComponentType>()
+            .on_replace
+            .push(Box::new(hook));
+    }
+
+    /// Runs `hook` right before a `ComponentType` is removed from an entity, either through
+    /// `remove_component` or because the entity is removed, so the value can still be read.
+    pub fn on_remove<ComponentType: Component>(
+        &mut self,
+        hook: impl Fn(&Ecs, Entity) -> Result<(), EcsError> + Send + Sync + 'static,
+    ) {
+        self.component_hooks::<ComponentType>()
+            .on_remove
+            .push(Box::new(hook));
+    }
+
+    fn component_hooks<ComponentType: Component>(&mut self) -> &mut ComponentHooks {
+        self.hooks.entry(TypeId::of::<ComponentType>()).or_default()
+    }
+
+    /// Whether any hook runs when a component of the type is removed.
+    pub(super) fn has_remove_hooks(&self, type_id: TypeId) -> bool {
+        self.hooks
+            .get(&type_id)
+            .is_some_and(|hooks| !hooks.on_remove.is_empty())
+    }
+
+    /// Runs the hooks of one kind in the order they were registered, stopping at the first
+    /// error.
+    pub(super) fn run_hooks(
+        &self,
+        type_id: TypeId,
+        kind: HookKind,
+        entity: Entity,
+    ) -> Result<(), EcsError> {
+        let Some(hooks) = self.hooks.get(&type_id) else {
+            return Ok(());
+        };
+
+        hooks
+            .get(kind)
+            .iter()
+            .try_for_each(|hook| hook(self, entity))
+    }
+}
@@ -0,0 +1,53 @@
+use super::{Ecs, EcsError, EntitySlot, StorageType};
+use std::{cmp::Reverse, mem::size_of};
+
+/// Memory held by one registered component type.
+#[derive(Clone, Debug)]
+pub struct ComponentMemory {
+    pub name: &'static str,
+    pub storage_type: StorageType,
+    pub bytes: usize,
+}
+
+/// Snapshot of the memory allocated by an [`Ecs`], see [`Ecs::memory_usage`].
+#[derive(Clone, Debug)]
+pub struct MemoryUsage {
+    pub entity_count: usize,
+    pub entity_bytes: usize,
+    pub components: Vec<ComponentMemory>,
+}
+
+impl MemoryUsage {
+    pub fn component_bytes(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component| component.bytes)
+            .sum()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.entity_bytes + self.component_bytes()
+    }
+}
+
+impl Ecs {
+    /// Reports how many entities are alive and how much memory the entity list and every
+    /// component storage have allocated. Fails if a component vector is borrowed mutably.
+    pub fn memory_usage(&self) -> Result<MemoryUsage, EcsError> {
+        let entity_bytes = self.entities.capacity() * size_of::<EntitySlot>()
+            + self.free_entities.capacity() * size_of::<usize>();
+
+        let mut components = self
+            .component_vecs
+            .values()
+            .map(|component_vec| component_vec.memory_usage())
+            .collect::<Result<Vec<_>, _>>()?;
+        components.sort_by_key(|component| Reverse(component.bytes));
+
+        Ok(MemoryUsage {
+            entity_count: self.entity_count(),
+            entity_bytes,
+            components,
+        })
+    }
+}
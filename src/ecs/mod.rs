@@ -1,128 +1,783 @@
+use archetype::{ArchetypeId, Archetypes, EMPTY_ARCHETYPE};
+use change_detection::{is_newer, CHECK_TICK_THRESHOLD};
 use component_vec::ComponentVec;
-use std::cell::{RefCell, RefMut};
+use hooks::{ComponentHooks, HookKind};
+use serialization::Serializer;
+use snapshot::Snapshotter;
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    mem::size_of,
+    sync::{Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError},
+    thread::ThreadId,
+};
 
+mod archetype;
+mod bundle;
+mod change_detection;
+mod commands;
 mod component_vec;
+mod events;
+mod hierarchy;
+mod hooks;
+mod memory;
+mod name;
+mod query;
+mod serialization;
+mod snapshot;
+mod sparse_set;
+mod storage;
+mod table;
+mod tag_set;
 
-const MAX_ENTITIES: usize = 5000;
+pub use bundle::Bundle;
+pub use change_detection::{clamp_tick, Added, Changed, ComponentTicks, Mut};
+pub use commands::{Commands, EntityCommands, LockedCommands};
+pub use events::{Event, EventReader, EventWriter, Events};
+pub use hierarchy::{Children, Parent};
+pub use hooks::ComponentHook;
+pub use memory::{ComponentMemory, MemoryUsage};
+pub use name::Name;
+pub use query::{Query, QueryData, QueryFilter, QueryIter, With, Without};
+pub use serialization::{
+    Serializable, SerializationError, ValueReader, ValueWriter, WorldFormat, FORMAT_VERSION,
+};
+pub use snapshot::{ChangeKind, ComponentChange, Snapshot, Snapshottable, WorldDiff};
+pub use sparse_set::SparseSet;
+pub use storage::{ComponentStorage, StorageType};
+pub use table::Table;
+pub use tag_set::TagSet;
 
-pub type Entity = usize;
+/// Anything that can be attached to an entity. Systems may run on worker threads, so components
+/// have to be shareable between them.
+pub trait Component: Send + Sync + 'static {}
+
+impl<T: Send + Sync + 'static> Component for T {}
+
+/// A world-wide singleton stored in the ECS, such as the `MeshManager`.
+pub trait Resource: Send + Sync + 'static {}
+
+impl<T: Send + Sync + 'static> Resource for T {}
+
+fn read_lock<T>(lock: &RwLock<T>) -> Result<RwLockReadGuard<'_, T>, EcsError> {
+    match lock.try_read() {
+        Ok(guard) => Ok(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => Err(EcsError::AlreadyBorrowed),
+    }
+}
+
+fn write_lock<T>(lock: &RwLock<T>) -> Result<RwLockWriteGuard<'_, T>, EcsError> {
+    match lock.try_write() {
+        Ok(guard) => Ok(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => Err(EcsError::AlreadyBorrowed),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: usize,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
 
 #[derive(Debug)]
 pub enum EcsError {
     UnregisteredComponent,
     OutOfRange,
     TooManyEntities,
+    StaleEntity,
+    DuplicateComponent,
+    MissingComponent,
+    AlreadyBorrowed,
+    MissingResource,
+    HierarchyCycle,
+}
+
+#[derive(Clone, Copy)]
+struct EntitySlot {
+    generation: u32,
+    alive: bool,
 }
 
 pub struct Ecs {
-    entity_count: usize,
-    component_vecs: Vec<Box<dyn ComponentVec>>,
+    entities: Vec<EntitySlot>,
+    free_entities: Vec<usize>,
+    /// Entities handed out by `reserve_entity` that have no slot yet, in the order
+    /// `create_entity` would have handed them out.
+    reserved: Mutex<Vec<Entity>>,
+    component_vecs: HashMap<TypeId, Box<dyn ComponentVec>>,
+    resources: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    commands: Mutex<Commands>,
+    event_updaters: Vec<fn(&Ecs)>,
+    auto_register: bool,
+    change_tick: u32,
+    last_change_tick: u32,
+    /// Change tick `change_tick` was at when ticks were last checked for their age.
+    last_tick_check: u32,
+    /// Entities that lost a component, with the change tick they lost it at.
+    removed_components: HashMap<TypeId, Vec<(Entity, u32)>>,
+    /// Removals after this tick are kept past `clear_trackers`, see `keep_removals_since`.
+    removals_kept_since: Option<u32>,
+    archetypes: Archetypes,
+    default_storage: StorageType,
+    capacity: usize,
+    entity_limit: Option<usize>,
+    serializers: BTreeMap<&'static str, Serializer>,
+    names: HashMap<String, Vec<Entity>>,
+    hooks: HashMap<TypeId, ComponentHooks>,
+    snapshotters: HashMap<TypeId, Snapshotter>,
 }
 
 impl Ecs {
     pub fn new() -> Self {
-        Self {
-            entity_count: 0,
-            component_vecs: Vec::new(),
+        Self::with_capacity(0)
+    }
+
+    /// Creates a world with room for `capacity` entities before any storage has to grow.
+    /// Worlds grow past their capacity as needed unless an entity limit is set.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut ecs = Self {
+            entities: Vec::with_capacity(capacity),
+            free_entities: Vec::new(),
+            reserved: Mutex::new(Vec::new()),
+            component_vecs: HashMap::new(),
+            resources: HashMap::new(),
+            commands: Mutex::new(Commands::new()),
+            event_updaters: Vec::new(),
+            auto_register: false,
+            change_tick: 1,
+            last_change_tick: 0,
+            last_tick_check: 1,
+            removed_components: HashMap::new(),
+            removals_kept_since: None,
+            archetypes: Archetypes::new(),
+            default_storage: StorageType::Dense,
+            capacity,
+            entity_limit: None,
+            serializers: BTreeMap::new(),
+            names: HashMap::new(),
+            hooks: HashMap::new(),
+            snapshotters: HashMap::new(),
+        };
+
+        // Few entities take part in the hierarchy, so its components are kept packed.
+        ecs.register_component_with_storage::<Parent>(StorageType::SparseSet)
+            .expect("Could not register parent component");
+        ecs.register_component_with_storage::<Children>(StorageType::SparseSet)
+            .expect("Could not register children component");
+        ecs.register_component_with_storage::<Name>(StorageType::SparseSet)
+            .expect("Could not register name component");
+        ecs.register_serializable::<Parent>();
+        ecs.register_serializable::<Children>();
+        ecs.register_serializable::<Name>();
+        ecs.register_snapshot::<Parent>();
+        ecs.register_snapshot::<Children>();
+        ecs.register_snapshot::<Name>();
+
+        ecs
+    }
+
+    fn validate(&self, entity: Entity) -> Result<(), EcsError> {
+        match self.entities.get(entity.index) {
+            None => Err(EcsError::OutOfRange),
+            Some(slot) if !slot.alive || slot.generation != entity.generation => {
+                Err(EcsError::StaleEntity)
+            }
+            Some(_) => Ok(()),
         }
     }
 
-    pub fn register_component<ComponentType: 'static>(&mut self) {
-        let mut new_component_vec: Vec<Option<ComponentType>> =
-            Vec::with_capacity(self.entity_count);
+    /// Caps how many entity slots the world may allocate, making `create_entity` fail with
+    /// `EcsError::TooManyEntities` once every slot is in use. `None` lets the world grow freely.
+    pub fn set_entity_limit(&mut self, limit: Option<usize>) {
+        self.entity_limit = limit;
+    }
+
+    /// When enabled, `add_component` registers unknown component types instead of failing with
+    /// `EcsError::UnregisteredComponent`.
+    pub fn set_auto_register(&mut self, auto_register: bool) {
+        self.auto_register = auto_register;
+    }
 
-        for _ in 0..self.entity_count {
-            new_component_vec.push(None);
+    /// Storage used by `register_component` and auto registration. `StorageType::Table`
+    /// switches the world to archetype storage, where queries walk the packed rows of every
+    /// archetype that can match. Only affects types registered afterwards.
+    pub fn set_default_storage(&mut self, storage_type: StorageType) {
+        self.default_storage = storage_type;
+    }
+
+    /// Registers with the default storage. Zero-sized tag components such as `Player` are
+    /// always stored as tags instead, one bit per entity index up to the highest tagged one.
+    pub fn register_component<ComponentType: Component>(&mut self) -> Result<(), EcsError> {
+        let storage_type = if size_of::<ComponentType>() == 0 {
+            StorageType::Tag
+        } else {
+            self.default_storage
+        };
+
+        self.register_component_with_storage::<ComponentType>(storage_type)
+    }
+
+    pub fn register_component_with_storage<ComponentType: Component>(
+        &mut self,
+        storage_type: StorageType,
+    ) -> Result<(), EcsError> {
+        let type_id = TypeId::of::<ComponentType>();
+        if self.component_vecs.contains_key(&type_id) {
+            return Err(EcsError::DuplicateComponent);
         }
 
+        let storage: ComponentStorage<ComponentType> =
+            ComponentStorage::new(storage_type, self.entities.len(), self.capacity);
         self.component_vecs
-            .push(Box::new(RefCell::new(new_component_vec)));
+            .insert(type_id, Box::new(RwLock::new(storage)));
+
+        Ok(())
     }
 
     pub fn create_entity(&mut self) -> Result<Entity, EcsError> {
-        if self.entity_count >= MAX_ENTITIES {
+        self.flush_reserved()?;
+        self.allocate_entity()
+    }
+
+    /// Hands out the entity `create_entity` will create next without creating it, so it can be
+    /// referred to before commands spawn it. It is created empty by the next structural change.
+    pub fn reserve_entity(&self) -> Result<Entity, EcsError> {
+        let mut reserved = self.reserved.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // Freed slots are reused from the back of the list first, as in `allocate_entity`.
+        let entity = match self.free_entities.len().checked_sub(reserved.len() + 1) {
+            Some(position) => self.entity_at(self.free_entities[position]),
+            None => {
+                let index = self.entities.len() + reserved.len() - self.free_entities.len();
+                if self.entity_limit.is_some_and(|limit| index >= limit) {
+                    return Err(EcsError::TooManyEntities);
+                }
+                Entity {
+                    index,
+                    generation: 0,
+                }
+            }
+        };
+        reserved.push(entity);
+
+        Ok(entity)
+    }
+
+    /// Creates the reserved entities. Runs before anything changes which slots are free.
+    fn flush_reserved(&mut self) -> Result<(), EcsError> {
+        let reserved = std::mem::take(
+            self.reserved
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+
+        reserved.into_iter().try_for_each(|entity| {
+            let created = self.allocate_entity()?;
+            debug_assert_eq!(created, entity, "Reserved entities are created in order");
+            Ok(())
+        })
+    }
+
+    fn allocate_entity(&mut self) -> Result<Entity, EcsError> {
+        // Reuse a freed slot before growing. Its generation was bumped on removal, so handles to
+        // the previous occupant stay invalid.
+        if let Some(index) = self.free_entities.pop() {
+            let slot = &mut self.entities[index];
+            slot.alive = true;
+
+            return Ok(Entity {
+                index,
+                generation: slot.generation,
+            });
+        }
+
+        if self
+            .entity_limit
+            .is_some_and(|limit| self.entities.len() >= limit)
+        {
             return Err(EcsError::TooManyEntities);
         }
-        let entity_id = self.entity_count;
-        for component_vec in self.component_vecs.iter_mut() {
+        let index = self.entities.len();
+        for component_vec in self.component_vecs.values_mut() {
             component_vec.push_none();
         }
-        self.entity_count += 1;
+        self.entities.push(EntitySlot {
+            generation: 0,
+            alive: true,
+        });
 
-        Ok(entity_id)
+        Ok(Entity {
+            index,
+            generation: 0,
+        })
     }
 
+    /// Removes the entity together with everything attached below it in the hierarchy.
     pub fn remove_entity(&mut self, entity: Entity) -> Result<(), EcsError> {
-        if entity >= self.entity_count {
-            return Err(EcsError::OutOfRange);
+        self.flush_reserved()?;
+        self.validate(entity)?;
+        self.remove_parent(entity)?;
+
+        self.run_remove_hooks(entity)?;
+
+        let children = self.take_children(entity);
+        self.clear_entity(entity);
+
+        let slot = &mut self.entities[entity.index];
+        slot.alive = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_entities.push(entity.index);
+
+        children
+            .into_iter()
+            .try_for_each(|child| self.remove_entity(child))
+    }
+
+    /// Runs the removal hooks of every component the entity has, while they are all still in
+    /// place.
+    fn run_remove_hooks(&self, entity: Entity) -> Result<(), EcsError> {
+        let hooked: Vec<TypeId> = self
+            .component_vecs
+            .iter()
+            .filter(|(type_id, component_vec)| {
+                self.has_remove_hooks(**type_id) && component_vec.contains(entity.index)
+            })
+            .map(|(type_id, _)| *type_id)
+            .collect();
+
+        hooked
+            .into_iter()
+            .try_for_each(|type_id| self.run_hooks(type_id, HookKind::Remove, entity))
+    }
+
+    /// Drops every component of the entity without running hooks.
+    fn clear_entity(&mut self, entity: Entity) {
+        self.unindex_name(entity);
+
+        for (type_id, component_vec) in self.component_vecs.iter_mut() {
+            if component_vec.clear(entity.index) {
+                self.removed_components
+                    .entry(*type_id)
+                    .or_default()
+                    .push((entity, self.change_tick));
+            }
         }
-        for component_vec in self.component_vecs.iter_mut() {
-            component_vec.remove(entity);
+
+        self.archetypes.set(entity.index, EMPTY_ARCHETYPE);
+    }
+
+    /// Handle of whatever currently occupies the slot at `index`, alive or not.
+    fn entity_at(&self, index: usize) -> Entity {
+        Entity {
+            index,
+            generation: self.entities[index].generation,
         }
+    }
 
-        Ok(())
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.validate(entity).is_ok()
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entities.len() - self.free_entities.len()
     }
 
-    pub fn add_component<ComponentType: 'static>(
+    pub fn add_component<ComponentType: Component>(
         &mut self,
         entity: Entity,
         component: ComponentType,
     ) -> Result<(), EcsError> {
-        for component_vec in self.component_vecs.iter_mut() {
-            if let Some(component_vec) = component_vec
-                .as_any_mut()
-                .downcast_mut::<RefCell<Vec<Option<ComponentType>>>>()
-            {
-                component_vec.get_mut()[entity] = Some(component);
-                return Ok(());
-            }
+        self.validate(entity)?;
+        if self.auto_register && !self.is_registered::<ComponentType>() {
+            self.register_component::<ComponentType>()?;
         }
 
-        Err(EcsError::UnregisteredComponent)
+        let kind = if self.insert_component(entity, component)? {
+            HookKind::Replace
+        } else {
+            HookKind::Add
+        };
+        self.run_hooks(TypeId::of::<ComponentType>(), kind, entity)
     }
 
-    pub fn get_component<ComponentType: 'static>(
+    /// Stores the component without running hooks, returning whether it replaced one.
+    fn insert_component<ComponentType: Component>(
         &mut self,
         entity: Entity,
-    ) -> Result<&mut Option<ComponentType>, EcsError> {
-        for component_vec in self.component_vecs.iter_mut() {
-            if let Some(component_vec) = component_vec
-                .as_any_mut()
-                .downcast_mut::<RefCell<Vec<Option<ComponentType>>>>()
-            {
-                if let Some(component) = component_vec.get_mut().get_mut(entity) {
-                    return Ok(component);
-                }
-                return Err(EcsError::OutOfRange);
-            }
+        component: ComponentType,
+    ) -> Result<bool, EcsError> {
+        let change_tick = self.change_tick;
+        let storage = self.component_vec_mut::<ComponentType>()?;
+        let is_replaced = storage.contains(entity.index);
+        let is_new_row = storage.storage_type() == StorageType::Table && !is_replaced;
+
+        let mut archetype = self.archetypes.get(entity.index);
+        if is_new_row {
+            archetype = self
+                .archetypes
+                .with(archetype, TypeId::of::<ComponentType>());
+            self.move_entity(entity.index, archetype);
         }
+        self.index_name(entity, &component);
+        self.component_vec_mut::<ComponentType>()?.insert(
+            entity.index,
+            component,
+            change_tick,
+            archetype,
+        );
 
-        Err(EcsError::UnregisteredComponent)
+        Ok(is_replaced)
     }
 
-    pub fn remove_component<ComponentType: 'static>(&mut self, entity: Entity) {
-        for component_vec in self.component_vecs.iter_mut() {
-            if let Some(component_vec) = component_vec
-                .as_any_mut()
-                .downcast_mut::<RefCell<Vec<Option<ComponentType>>>>()
-            {
-                component_vec.get_mut()[entity] = None;
+    /// Moves the entity's table stored components into the columns of another archetype.
+    fn move_entity(&mut self, index: usize, archetype: ArchetypeId) {
+        let current = self.archetypes.get(index);
+        for type_id in self.archetypes.components(current) {
+            if let Some(component_vec) = self.component_vecs.get_mut(type_id) {
+                component_vec.move_to(index, archetype);
             }
         }
+        self.archetypes.set(index, archetype);
+    }
+
+    /// Mutable access to one component. There is no way to tell whether the caller writes to
+    /// it, so the component is always flagged as changed.
+    pub fn get_component<ComponentType: Component>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<Option<&mut ComponentType>, EcsError> {
+        self.validate(entity)?;
+
+        let change_tick = self.change_tick;
+        Ok(self
+            .component_vec_mut::<ComponentType>()?
+            .get_with_ticks_mut(entity.index)
+            .map(|(component, ticks)| {
+                ticks.set_changed(change_tick);
+                component
+            }))
+    }
+
+    pub fn remove_component<ComponentType: Component>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<(), EcsError> {
+        self.validate(entity)?;
+        if !self
+            .component_vec_mut::<ComponentType>()?
+            .contains(entity.index)
+        {
+            return Ok(());
+        }
+        self.run_hooks(TypeId::of::<ComponentType>(), HookKind::Remove, entity)?;
+        self.take_component::<ComponentType>(entity)?;
+
+        Ok(())
     }
 
-    pub fn get_component_vec<ComponentType: 'static>(
+    /// Removes the component without running hooks.
+    fn take_component<ComponentType: Component>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<Option<ComponentType>, EcsError> {
+        if TypeId::of::<ComponentType>() == TypeId::of::<Name>() {
+            self.unindex_name(entity);
+        }
+
+        let storage = self.component_vec_mut::<ComponentType>()?;
+        let is_table = storage.storage_type() == StorageType::Table;
+        let Some(component) = storage.remove(entity.index) else {
+            return Ok(None);
+        };
+        if is_table {
+            let archetype = self.archetypes.without(
+                self.archetypes.get(entity.index),
+                TypeId::of::<ComponentType>(),
+            );
+            self.move_entity(entity.index, archetype);
+        }
+
+        self.removed_components
+            .entry(TypeId::of::<ComponentType>())
+            .or_default()
+            .push((entity, self.change_tick));
+
+        Ok(Some(component))
+    }
+
+    /// Entities that lost their `ComponentType`, either through `remove_component` or by being
+    /// removed, since trackers were last cleared.
+    pub fn removed_components<ComponentType: Component>(&self) -> Vec<Entity> {
+        self.removed_components_since::<ComponentType>(self.last_change_tick)
+    }
+
+    /// Entities that lost their `ComponentType` after the change tick `last_run`, in the order
+    /// they lost it. Systems pass the tick they last ran at, see `keep_removals_since`.
+    pub fn removed_components_since<ComponentType: Component>(&self, last_run: u32) -> Vec<Entity> {
+        self.removed_components
+            .get(&TypeId::of::<ComponentType>())
+            .into_iter()
+            .flatten()
+            .filter(|(_, tick)| is_newer(*tick, last_run, self.change_tick))
+            .map(|(entity, _)| *entity)
+            .collect()
+    }
+
+    /// Tick that components added or written to right now are stamped with.
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick
+    }
+
+    /// Moves on to a new change tick and returns it, so that changes made from now on can be
+    /// told apart from earlier ones. The schedule calls this around every batch of systems.
+    pub fn advance_change_tick(&mut self) -> u32 {
+        self.change_tick = self.change_tick.wrapping_add(1);
+        self.change_tick
+    }
+
+    /// Tick of the last `clear_trackers`, what `Added` and `Changed` in [`Ecs::query_filtered`]
+    /// compare against.
+    pub fn last_change_tick(&self) -> u32 {
+        self.last_change_tick
+    }
+
+    /// Keeps removals after the change tick `since` around when trackers are cleared, so
+    /// systems that last ran at or after `since` still get them from `removed_components_since`.
+    /// The schedule calls this with the oldest last run of its systems.
+    pub fn keep_removals_since(&mut self, since: u32) {
+        self.removals_kept_since = Some(since);
+    }
+
+    /// Starts a new change detection frame: `removed_components`, and `Added` and `Changed`
+    /// in [`Ecs::query_filtered`], only report what happens after this call. Call once per
+    /// frame, after every system has run.
+    pub fn clear_trackers(&mut self) {
+        self.last_change_tick = self.change_tick;
+        self.change_tick = self.change_tick.wrapping_add(1);
+
+        let since = self.removals_kept_since.unwrap_or(self.last_change_tick);
+        let change_tick = self.change_tick;
+        for removed in self.removed_components.values_mut() {
+            removed.retain(|(_, tick)| is_newer(*tick, since, change_tick));
+        }
+
+        if change_tick.wrapping_sub(self.last_tick_check) >= CHECK_TICK_THRESHOLD {
+            self.check_change_ticks();
+        }
+    }
+
+    /// Clamps every change tick older than `MAX_CHANGE_AGE`, so they still read as old once
+    /// `change_tick` wraps around.
+    fn check_change_ticks(&mut self) {
+        let change_tick = self.change_tick;
+        for component_vec in self.component_vecs.values_mut() {
+            component_vec.clamp_ticks(change_tick);
+        }
+        for removed in self.removed_components.values_mut() {
+            removed
+                .iter_mut()
+                .for_each(|(_, tick)| clamp_tick(tick, change_tick));
+        }
+        clamp_tick(&mut self.last_change_tick, change_tick);
+        if let Some(since) = self.removals_kept_since.as_mut() {
+            clamp_tick(since, change_tick);
+        }
+        self.last_tick_check = change_tick;
+    }
+
+    pub fn is_registered<ComponentType: Component>(&self) -> bool {
+        self.component_vecs
+            .contains_key(&TypeId::of::<ComponentType>())
+    }
+
+    fn component_vec_mut<ComponentType: Component>(
+        &mut self,
+    ) -> Result<&mut ComponentStorage<ComponentType>, EcsError> {
+        self.component_vecs
+            .get_mut(&TypeId::of::<ComponentType>())
+            .and_then(|component_vec| {
+                component_vec
+                    .as_any_mut()
+                    .downcast_mut::<RwLock<ComponentStorage<ComponentType>>>()
+            })
+            .map(|storage| storage.get_mut().unwrap_or_else(PoisonError::into_inner))
+            .ok_or(EcsError::UnregisteredComponent)
+    }
+
+    fn component_cell<ComponentType: Component>(
+        &self,
+    ) -> Result<&RwLock<ComponentStorage<ComponentType>>, EcsError> {
+        self.component_vecs
+            .get(&TypeId::of::<ComponentType>())
+            .and_then(|component_vec| {
+                component_vec
+                    .as_any()
+                    .downcast_ref::<RwLock<ComponentStorage<ComponentType>>>()
+            })
+            .ok_or(EcsError::UnregisteredComponent)
+    }
+
+    /// Locks the storage of `ComponentType` for reading and writing. Components are looked up
+    /// by entity index with `get` and `get_mut`, and `iter` yields the entities that have one
+    /// along with it.
+    pub fn get_component_vec<ComponentType: Component>(
+        &self,
+    ) -> Result<RwLockWriteGuard<'_, ComponentStorage<ComponentType>>, EcsError> {
+        write_lock(self.component_cell::<ComponentType>()?)
+    }
+
+    /// Borrows the components described by `Q` for every living entity, e.g.
+    /// `ecs.query::<(&mut Transform, &RigidBody, Option<&GravityComponent>)>()`.
+    pub fn query<Q: QueryData>(&self) -> Result<Query<'_, Q>, EcsError> {
+        Query::new(self, self.last_change_tick)
+    }
+
+    /// Same as [`Ecs::query`], but only visits entities passing the filter `F`, e.g.
+    /// `With<Controllable>` or `(With<RigidBody>, Without<GravityComponent>)`.
+    /// `Added` and `Changed` report changes since trackers were last cleared.
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(
+        &self,
+    ) -> Result<Query<'_, Q, F>, EcsError> {
+        Query::new(self, self.last_change_tick)
+    }
+
+    /// Same as [`Ecs::query_filtered`], but `Added` and `Changed` report changes after the
+    /// change tick `last_run`, e.g. the last run of the system making the query.
+    pub fn query_filtered_since<Q: QueryData, F: QueryFilter>(
+        &self,
+        last_run: u32,
+    ) -> Result<Query<'_, Q, F>, EcsError> {
+        Query::new(self, last_run)
+    }
+
+    /// Stores a world-wide singleton, replacing any previous resource of the same type.
+    pub fn insert_resource<ResourceType: Resource>(&mut self, resource: ResourceType) {
+        self.resources.insert(
+            TypeId::of::<ResourceType>(),
+            Box::new(RwLock::new(resource)),
+        );
+    }
+
+    pub fn remove_resource<ResourceType: Resource>(&mut self) -> Option<ResourceType> {
+        let resource = self.resources.remove(&TypeId::of::<ResourceType>())?;
+        let resource = resource.downcast::<RwLock<ResourceType>>().ok()?;
+
+        Some(
+            resource
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    pub fn has_resource<ResourceType: Resource>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<ResourceType>())
+    }
+
+    fn resource_cell<ResourceType: Resource>(&self) -> Result<&RwLock<ResourceType>, EcsError> {
+        self.resources
+            .get(&TypeId::of::<ResourceType>())
+            .and_then(|resource| resource.downcast_ref::<RwLock<ResourceType>>())
+            .ok_or(EcsError::MissingResource)
+    }
+
+    pub fn resource<ResourceType: Resource>(
+        &self,
+    ) -> Result<RwLockReadGuard<'_, ResourceType>, EcsError> {
+        read_lock(self.resource_cell::<ResourceType>()?)
+    }
+
+    pub fn resource_mut<ResourceType: Resource>(
+        &self,
+    ) -> Result<RwLockWriteGuard<'_, ResourceType>, EcsError> {
+        write_lock(self.resource_cell::<ResourceType>()?)
+    }
+
+    /// Registers `T` as an event type by storing an empty [`Events<T>`] resource. Registering
+    /// the same type again keeps the existing queue.
+    pub fn add_event<EventType: Event>(&mut self) {
+        if self.has_resource::<Events<EventType>>() {
+            return;
+        }
+
+        self.insert_resource(Events::<EventType>::new());
+        self.event_updaters.push(Events::<EventType>::update_system);
+    }
+
+    pub fn event_writer<EventType: Event>(&self) -> Result<EventWriter<'_, EventType>, EcsError> {
+        EventWriter::new(self)
+    }
+
+    pub fn events<EventType: Event>(
         &self,
-    ) -> Result<RefMut<Vec<Option<ComponentType>>>, EcsError> {
-        for component_vec in self.component_vecs.iter() {
-            if let Some(component_vec) = component_vec
-                .as_any()
-                .downcast_ref::<RefCell<Vec<Option<ComponentType>>>>()
-            {
-                return Ok(component_vec.borrow_mut());
+    ) -> Result<RwLockReadGuard<'_, Events<EventType>>, EcsError> {
+        self.resource::<Events<EventType>>()
+    }
+
+    /// Swaps the buffers of every registered event queue. Call once per update so events live
+    /// for exactly two of them.
+    pub fn update_events(&self) {
+        for update in self.event_updaters.iter() {
+            update(self);
+        }
+    }
+
+    /// Queue for structural changes that cannot be made while components are borrowed. Systems
+    /// running in parallel share the queue, so the guard should not be held longer than needed.
+    pub fn commands(&self) -> LockedCommands<'_> {
+        LockedCommands::new(
+            self,
+            self.commands.lock().unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Reorders queued commands so that everything queued from `threads[0]` runs first, then
+    /// `threads[1]` and so on, keeping the queue order within each thread. Used by the schedule
+    /// to make systems that ran in parallel apply their commands in a deterministic order.
+    pub fn order_commands_by_thread(&mut self, threads: &[ThreadId]) {
+        self.commands
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .sort_by_thread(threads);
+    }
+
+    /// Sync point for deferred changes. Commands queued while applying run in the same call.
+    pub fn apply_commands(&mut self) -> Result<(), EcsError> {
+        let mut result = Ok(());
+
+        loop {
+            // Entities reserved by the commands are created before any of them run.
+            let flushed = self.flush_reserved();
+            if result.is_ok() {
+                result = flushed;
+            }
+
+            let commands = std::mem::take(
+                self.commands
+                    .get_mut()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+            if commands.is_empty() {
+                break;
+            }
+
+            let applied = commands.apply(self);
+            if result.is_ok() {
+                result = applied;
             }
         }
 
-        Err(EcsError::UnregisteredComponent)
+        result
     }
 }
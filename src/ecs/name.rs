@@ -0,0 +1,96 @@
+use super::{read_lock, Component, Ecs, EcsError, Entity};
+use std::{any::Any, fmt};
+
+/// Human readable label for an entity, e.g. `"player"` or `"Wall 1"`. Names do not have to be
+/// unique, see [`Ecs::find_by_name`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Name(String);
+
+impl Name {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Ecs {
+    /// The first entity that was given `name` and still has it, or `None`. Names set through
+    /// `add_component` are found through an index; names overwritten in place through a query
+    /// are only found by scanning every name.
+    pub fn find_by_name(&self, name: &str) -> Result<Option<Entity>, EcsError> {
+        let names = read_lock(self.component_cell::<Name>()?)?;
+        let has_name = |index: usize| names.get(index).is_some_and(|other| other.as_str() == name);
+
+        let indexed = self
+            .names
+            .get(name)
+            .and_then(|entities| {
+                entities
+                    .iter()
+                    .find(|entity| self.is_alive(**entity) && has_name(entity.index))
+            })
+            .copied();
+        if indexed.is_some() {
+            return Ok(indexed);
+        }
+
+        let scanned = names
+            .iter()
+            .find(|(_, other)| other.as_str() == name)
+            .map(|(index, _)| self.entity_at(index));
+
+        Ok(scanned)
+    }
+
+    /// Records the entity under its new name if `component` is a [`Name`].
+    pub(super) fn index_name<ComponentType: Component>(
+        &mut self,
+        entity: Entity,
+        component: &ComponentType,
+    ) {
+        let Some(name) = (component as &dyn Any).downcast_ref::<Name>() else {
+            return;
+        };
+
+        self.unindex_name(entity);
+        self.names.entry(name.0.clone()).or_default().push(entity);
+    }
+
+    /// Drops the entity from the name index, if it was in there.
+    pub(super) fn unindex_name(&mut self, entity: Entity) {
+        let Some(name) = self
+            .component_vec_mut::<Name>()
+            .ok()
+            .and_then(|names| names.get(entity.index))
+            .map(|name| name.0.clone())
+        else {
+            return;
+        };
+
+        if let Some(entities) = self.names.get_mut(&name) {
+            if let Some(position) = entities.iter().position(|other| *other == entity) {
+                entities.remove(position);
+                if entities.is_empty() {
+                    self.names.remove(&name);
+                }
+                return;
+            }
+        }
+
+        // The name was overwritten through a query after it was indexed, so the old entry has
+        // to be searched for.
+        self.names.retain(|_, entities| {
+            entities.retain(|other| *other != entity);
+            !entities.is_empty()
+        });
+    }
+}
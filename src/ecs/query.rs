@@ -0,0 +1,633 @@
+use super::{
+    archetype::ArchetypeId,
+    change_detection::Mut,
+    read_lock,
+    storage::{ComponentStorage, Tracked},
+    write_lock, Component, Ecs, EcsError, Entity,
+};
+use std::{
+    marker::PhantomData,
+    sync::{RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// Component access that can be requested from a [`Query`]: `&T`, `&mut T`, `Option<Q>` or a
+/// tuple of those. `&mut T` yields a [`Mut`] that flags the component as changed when written.
+///
+/// # Safety
+///
+/// `fetch` hands out references that outlive the borrow of `state`. Implementors must only
+/// return data owned by the borrowed component vector, and callers must never fetch the same
+/// index twice while a previously fetched item is still alive.
+pub unsafe trait QueryData {
+    type State<'w>;
+    type Item<'q>;
+    /// One archetype's rows, for walking table columns without looking entities up.
+    type Column<'q>;
+
+    fn borrow_state(ecs: &Ecs) -> Result<Self::State<'_>, EcsError>;
+
+    /// Entity indices that could match, if the borrowed storage can list them without scanning
+    /// every entity. Every slice is visited in order.
+    fn packed_entities<'a>(state: &'a Self::State<'_>) -> Option<Vec<&'a [usize]>>;
+
+    /// Which archetypes entities have to be in to match.
+    fn archetypes(state: &Self::State<'_>) -> Archetypes;
+
+    /// Entity indices of the archetype's rows, if a table stored component lists them.
+    fn column_entities<'a>(
+        state: &'a Self::State<'_>,
+        archetype: ArchetypeId,
+    ) -> Option<&'a [usize]>;
+
+    /// # Safety
+    ///
+    /// See the trait level documentation.
+    unsafe fn fetch<'q>(state: &mut Self::State<'_>, index: usize) -> Option<Self::Item<'q>>;
+
+    /// # Safety
+    ///
+    /// The column must not outlive `state`, and no other column of the same archetype may be
+    /// alive.
+    unsafe fn column<'q>(state: &mut Self::State<'_>, archetype: ArchetypeId) -> Self::Column<'q>;
+
+    /// Fetches the item at `row` of the column, which belongs to the entity at `index`.
+    ///
+    /// # Safety
+    ///
+    /// See the trait level documentation, rows are fetched at most once like indices.
+    unsafe fn fetch_row<'q>(
+        column: &mut Self::Column<'q>,
+        state: &mut Self::State<'_>,
+        row: usize,
+        index: usize,
+    ) -> Option<Self::Item<'q>>;
+}
+
+/// The archetypes a [`QueryData`] can match entities in.
+pub enum Archetypes {
+    /// Entities of any archetype may match, e.g. for `Option<Q>`.
+    Any,
+    /// Only entities of these archetypes match, every required component is table stored.
+    Only(Vec<ArchetypeId>),
+    /// Some required component isn't table stored, so entities are matched one by one.
+    PerEntity,
+}
+
+impl Archetypes {
+    fn of<T>(storage: &ComponentStorage<T>) -> Self {
+        storage
+            .archetypes()
+            .map_or(Archetypes::PerEntity, Archetypes::Only)
+    }
+
+    /// Archetypes matching both `self` and `other`.
+    fn and(self, other: Archetypes) -> Archetypes {
+        match (self, other) {
+            (Archetypes::PerEntity, _) | (_, Archetypes::PerEntity) => Archetypes::PerEntity,
+            (Archetypes::Any, archetypes) | (archetypes, Archetypes::Any) => archetypes,
+            (Archetypes::Only(mut archetypes), Archetypes::Only(other)) => {
+                archetypes.retain(|archetype| other.contains(archetype));
+                Archetypes::Only(archetypes)
+            }
+        }
+    }
+}
+
+/// Restricts which entities a [`Query`] visits without borrowing their components mutably.
+pub trait QueryFilter {
+    type State<'w>;
+
+    /// `last_run` is the change tick `Added` and `Changed` report changes after.
+    fn borrow_state(ecs: &Ecs, last_run: u32) -> Result<Self::State<'_>, EcsError>;
+
+    fn matches(state: &Self::State<'_>, index: usize) -> bool;
+}
+
+/// Only matches entities that have a `T` component.
+pub struct With<T>(PhantomData<T>);
+
+/// Only matches entities that do not have a `T` component.
+pub struct Without<T>(PhantomData<T>);
+
+unsafe impl<T: Component> QueryData for &T {
+    type State<'w> = RwLockReadGuard<'w, ComponentStorage<T>>;
+    type Item<'q> = &'q T;
+    type Column<'q> = Option<&'q [Tracked<T>]>;
+
+    fn borrow_state(ecs: &Ecs) -> Result<Self::State<'_>, EcsError> {
+        read_lock(ecs.component_cell::<T>()?)
+    }
+
+    fn packed_entities<'a>(state: &'a Self::State<'_>) -> Option<Vec<&'a [usize]>> {
+        state.packed_entities()
+    }
+
+    fn archetypes(state: &Self::State<'_>) -> Archetypes {
+        Archetypes::of(state)
+    }
+
+    fn column_entities<'a>(
+        state: &'a Self::State<'_>,
+        archetype: ArchetypeId,
+    ) -> Option<&'a [usize]> {
+        state.column_entities(archetype)
+    }
+
+    unsafe fn fetch<'q>(state: &mut Self::State<'_>, index: usize) -> Option<Self::Item<'q>> {
+        let component = state.get(index)?;
+
+        Some(&*(component as *const T))
+    }
+
+    unsafe fn column<'q>(state: &mut Self::State<'_>, archetype: ArchetypeId) -> Self::Column<'q> {
+        let column = state.column(archetype)?;
+
+        Some(&*(column as *const [Tracked<T>]))
+    }
+
+    unsafe fn fetch_row<'q>(
+        column: &mut Self::Column<'q>,
+        state: &mut Self::State<'_>,
+        row: usize,
+        index: usize,
+    ) -> Option<Self::Item<'q>> {
+        match column {
+            Some(column) => Some(&column.get(row)?.component),
+            None => Self::fetch(state, index),
+        }
+    }
+}
+
+unsafe impl<T: Component> QueryData for &mut T {
+    type State<'w> = (RwLockWriteGuard<'w, ComponentStorage<T>>, u32);
+    type Item<'q> = Mut<'q, T>;
+    /// Start and length of the column, rows are handed out one at a time.
+    type Column<'q> = Option<(*mut Tracked<T>, usize)>;
+
+    fn borrow_state(ecs: &Ecs) -> Result<Self::State<'_>, EcsError> {
+        Ok((write_lock(ecs.component_cell::<T>()?)?, ecs.change_tick))
+    }
+
+    fn packed_entities<'a>((storage, _): &'a Self::State<'_>) -> Option<Vec<&'a [usize]>> {
+        storage.packed_entities()
+    }
+
+    fn archetypes((storage, _): &Self::State<'_>) -> Archetypes {
+        Archetypes::of(storage)
+    }
+
+    fn column_entities<'a>(
+        (storage, _): &'a Self::State<'_>,
+        archetype: ArchetypeId,
+    ) -> Option<&'a [usize]> {
+        storage.column_entities(archetype)
+    }
+
+    unsafe fn column<'q>(
+        (storage, _): &mut Self::State<'_>,
+        archetype: ArchetypeId,
+    ) -> Self::Column<'q> {
+        let column = storage.column_mut(archetype)?;
+
+        Some((column.as_mut_ptr(), column.len()))
+    }
+
+    unsafe fn fetch_row<'q>(
+        column: &mut Self::Column<'q>,
+        state: &mut Self::State<'_>,
+        row: usize,
+        index: usize,
+    ) -> Option<Self::Item<'q>> {
+        match *column {
+            Some((start, len)) if row < len => {
+                let tracked = &mut *start.add(row);
+
+                Some(Mut::new(
+                    &mut tracked.component,
+                    &mut tracked.ticks,
+                    state.1,
+                ))
+            }
+            Some(_) => None,
+            None => Self::fetch(state, index),
+        }
+    }
+
+    unsafe fn fetch<'q>(
+        (storage, change_tick): &mut Self::State<'_>,
+        index: usize,
+    ) -> Option<Self::Item<'q>> {
+        let (component, ticks) = storage.get_with_ticks_mut(index)?;
+
+        Some(Mut::new(
+            &mut *(component as *mut T),
+            &mut *(ticks as *mut _),
+            *change_tick,
+        ))
+    }
+}
+
+unsafe impl<Q: QueryData> QueryData for Option<Q> {
+    type State<'w> = Q::State<'w>;
+    type Item<'q> = Option<Q::Item<'q>>;
+    type Column<'q> = Q::Column<'q>;
+
+    fn borrow_state(ecs: &Ecs) -> Result<Self::State<'_>, EcsError> {
+        Q::borrow_state(ecs)
+    }
+
+    fn packed_entities<'a>(_state: &'a Self::State<'_>) -> Option<Vec<&'a [usize]>> {
+        None
+    }
+
+    fn archetypes(_state: &Self::State<'_>) -> Archetypes {
+        Archetypes::Any
+    }
+
+    fn column_entities<'a>(
+        _state: &'a Self::State<'_>,
+        _archetype: ArchetypeId,
+    ) -> Option<&'a [usize]> {
+        None
+    }
+
+    unsafe fn fetch<'q>(state: &mut Self::State<'_>, index: usize) -> Option<Self::Item<'q>> {
+        Some(Q::fetch(state, index))
+    }
+
+    unsafe fn column<'q>(state: &mut Self::State<'_>, archetype: ArchetypeId) -> Self::Column<'q> {
+        Q::column(state, archetype)
+    }
+
+    unsafe fn fetch_row<'q>(
+        column: &mut Self::Column<'q>,
+        state: &mut Self::State<'_>,
+        row: usize,
+        index: usize,
+    ) -> Option<Self::Item<'q>> {
+        Some(Q::fetch_row(column, state, row, index))
+    }
+}
+
+impl<T: Component> QueryFilter for With<T> {
+    type State<'w> = RwLockReadGuard<'w, ComponentStorage<T>>;
+
+    fn borrow_state(ecs: &Ecs, _last_run: u32) -> Result<Self::State<'_>, EcsError> {
+        <&T as QueryData>::borrow_state(ecs)
+    }
+
+    fn matches(state: &Self::State<'_>, index: usize) -> bool {
+        state.contains(index)
+    }
+}
+
+impl<T: Component> QueryFilter for Without<T> {
+    type State<'w> = RwLockReadGuard<'w, ComponentStorage<T>>;
+
+    fn borrow_state(ecs: &Ecs, _last_run: u32) -> Result<Self::State<'_>, EcsError> {
+        <&T as QueryData>::borrow_state(ecs)
+    }
+
+    fn matches(state: &Self::State<'_>, index: usize) -> bool {
+        !state.contains(index)
+    }
+}
+
+impl QueryFilter for () {
+    type State<'w> = ();
+
+    fn borrow_state(_ecs: &Ecs, _last_run: u32) -> Result<Self::State<'_>, EcsError> {
+        Ok(())
+    }
+
+    fn matches(_state: &Self::State<'_>, _index: usize) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($name:ident $column:ident),+) => {
+        #[allow(non_snake_case)]
+        unsafe impl<$($name: QueryData),+> QueryData for ($($name,)+) {
+            type State<'w> = ($($name::State<'w>,)+);
+            type Item<'q> = ($($name::Item<'q>,)+);
+            type Column<'q> = ($($name::Column<'q>,)+);
+
+            fn borrow_state(ecs: &Ecs) -> Result<Self::State<'_>, EcsError> {
+                Ok(($($name::borrow_state(ecs)?,)+))
+            }
+
+            fn packed_entities<'a>(state: &'a Self::State<'_>) -> Option<Vec<&'a [usize]>> {
+                let ($($name,)+) = state;
+
+                // Walking the smallest packed storage visits the fewest candidates.
+                [$($name::packed_entities($name)),+]
+                    .into_iter()
+                    .flatten()
+                    .min_by_key(|entities| entities.iter().map(|slice| slice.len()).sum::<usize>())
+            }
+
+            fn archetypes(state: &Self::State<'_>) -> Archetypes {
+                let ($($name,)+) = state;
+
+                Archetypes::Any$(.and($name::archetypes($name)))+
+            }
+
+            fn column_entities<'a>(
+                state: &'a Self::State<'_>,
+                archetype: ArchetypeId,
+            ) -> Option<&'a [usize]> {
+                let ($($name,)+) = state;
+
+                None$(.or_else(|| $name::column_entities($name, archetype)))+
+            }
+
+            unsafe fn fetch<'q>(
+                state: &mut Self::State<'_>,
+                index: usize,
+            ) -> Option<Self::Item<'q>> {
+                let ($($name,)+) = state;
+
+                Some(($($name::fetch($name, index)?,)+))
+            }
+
+            unsafe fn column<'q>(
+                state: &mut Self::State<'_>,
+                archetype: ArchetypeId,
+            ) -> Self::Column<'q> {
+                let ($($name,)+) = state;
+
+                ($($name::column($name, archetype),)+)
+            }
+
+            unsafe fn fetch_row<'q>(
+                column: &mut Self::Column<'q>,
+                state: &mut Self::State<'_>,
+                row: usize,
+                index: usize,
+            ) -> Option<Self::Item<'q>> {
+                let ($($name,)+) = state;
+                let ($($column,)+) = column;
+
+                Some(($($name::fetch_row($column, $name, row, index)?,)+))
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<$($name: QueryFilter),+> QueryFilter for ($($name,)+) {
+            type State<'w> = ($($name::State<'w>,)+);
+
+            fn borrow_state(ecs: &Ecs, last_run: u32) -> Result<Self::State<'_>, EcsError> {
+                Ok(($($name::borrow_state(ecs, last_run)?,)+))
+            }
+
+            fn matches(state: &Self::State<'_>, index: usize) -> bool {
+                let ($($name,)+) = state;
+
+                $($name::matches($name, index))&&+
+            }
+        }
+    };
+}
+
+impl_query_tuple!(A a);
+impl_query_tuple!(A a, B b);
+impl_query_tuple!(A a, B b, C c);
+impl_query_tuple!(A a, B b, C c, D d);
+impl_query_tuple!(A a, B b, C c, D d, E e);
+impl_query_tuple!(A a, B b, C c, D d, E e, F f);
+impl_query_tuple!(A a, B b, C c, D d, E e, F f, G g);
+impl_query_tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// Borrowed view over every living entity that has the components requested by `Q` and passes
+/// the filter `F`. The component vectors stay borrowed until the query is dropped.
+pub struct Query<'w, Q: QueryData, F: QueryFilter = ()> {
+    ecs: &'w Ecs,
+    data: Q::State<'w>,
+    filter: F::State<'w>,
+}
+
+impl<'w, Q: QueryData, F: QueryFilter> Query<'w, Q, F> {
+    /// Borrows the query, with `Added` and `Changed` reporting changes after `last_run`.
+    pub(super) fn new(ecs: &'w Ecs, last_run: u32) -> Result<Self, EcsError> {
+        Ok(Self {
+            ecs,
+            data: Q::borrow_state(ecs)?,
+            filter: F::borrow_state(ecs, last_run)?,
+        })
+    }
+
+    pub fn iter(&mut self) -> QueryIter<'_, 'w, Q, F> {
+        let walk = match Q::archetypes(&self.data) {
+            Archetypes::Only(archetypes) => Walk::Archetypes {
+                archetypes: archetypes.into_iter(),
+                rows: None,
+            },
+            Archetypes::Any | Archetypes::PerEntity => Walk::Entities {
+                candidates: Q::packed_entities(&self.data).map(|entities| entities.concat()),
+                position: 0,
+            },
+        };
+
+        QueryIter { query: self, walk }
+    }
+
+    pub fn get(&mut self, entity: Entity) -> Result<Q::Item<'_>, EcsError> {
+        self.ecs.validate(entity)?;
+
+        if !F::matches(&self.filter, entity.index) {
+            return Err(EcsError::MissingComponent);
+        }
+
+        // SAFETY: The returned item borrows the query mutably, so no other item can be alive.
+        unsafe { Q::fetch(&mut self.data, entity.index) }.ok_or(EcsError::MissingComponent)
+    }
+}
+
+pub struct QueryIter<'q, 'w, Q: QueryData, F: QueryFilter> {
+    query: &'q mut Query<'w, Q, F>,
+    walk: Walk<'q, Q>,
+}
+
+/// How a [`QueryIter`] finds the entities to visit.
+enum Walk<'q, Q: QueryData> {
+    /// Looks every candidate, or every entity, up by index.
+    Entities {
+        candidates: Option<Vec<usize>>,
+        position: usize,
+    },
+    /// Walks the table columns of the matching archetypes row by row.
+    Archetypes {
+        archetypes: std::vec::IntoIter<ArchetypeId>,
+        rows: Option<ArchetypeRows<'q, Q>>,
+    },
+}
+
+/// The rows of the archetype being walked.
+struct ArchetypeRows<'q, Q: QueryData> {
+    column: Q::Column<'q>,
+    /// Entity indices of the rows. Points into one of the borrowed table storages, which stay
+    /// locked and unchanged for as long as the query lives.
+    entities: *const [usize],
+    row: usize,
+}
+
+impl<'q, 'w, Q: QueryData, F: QueryFilter> QueryIter<'q, 'w, Q, F> {
+    fn next_entity(&mut self) -> Option<usize> {
+        let Walk::Entities {
+            candidates,
+            position,
+        } = &mut self.walk
+        else {
+            return None;
+        };
+
+        loop {
+            let index = match candidates {
+                Some(candidates) => *candidates.get(*position)?,
+                None if *position < self.query.ecs.entities.len() => *position,
+                None => return None,
+            };
+            *position += 1;
+
+            if self.query.ecs.entities[index].alive && F::matches(&self.query.filter, index) {
+                return Some(index);
+            }
+        }
+    }
+
+    fn entity(&self, index: usize) -> Entity {
+        Entity {
+            index,
+            generation: self.query.ecs.entities[index].generation,
+        }
+    }
+}
+
+impl<'q, 'w, Q: QueryData, F: QueryFilter> Iterator for QueryIter<'q, 'w, Q, F> {
+    type Item = (Entity, Q::Item<'q>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Walk::Entities { .. } = self.walk {
+            loop {
+                let index = self.next_entity()?;
+
+                // SAFETY: Every index is visited at most once, so items never alias each other.
+                if let Some(item) = unsafe { Q::fetch(&mut self.query.data, index) } {
+                    return Some((self.entity(index), item));
+                }
+            }
+        }
+
+        loop {
+            let Walk::Archetypes { archetypes, rows } = &mut self.walk else {
+                return None;
+            };
+
+            if let Some(rows) = rows {
+                // SAFETY: The entity list lives in a storage the query keeps locked.
+                let entities = unsafe { &*rows.entities };
+                if let Some(&index) = entities.get(rows.row) {
+                    let row = rows.row;
+                    rows.row += 1;
+                    if !F::matches(&self.query.filter, index) {
+                        continue;
+                    }
+
+                    // SAFETY: Every row is visited at most once, so items never alias each
+                    // other. Entities only have table stored components while they are alive.
+                    let item =
+                        unsafe { Q::fetch_row(&mut rows.column, &mut self.query.data, row, index) };
+                    if let Some(item) = item {
+                        return Some((self.entity(index), item));
+                    }
+                    continue;
+                }
+            }
+
+            let archetype = archetypes.next()?;
+            let entities = Q::column_entities(&self.query.data, archetype)
+                .expect("Table stored query without table stored components")
+                as *const [usize];
+            // SAFETY: Each archetype is visited once, so its column is the only one alive.
+            let column = unsafe { Q::column(&mut self.query.data, archetype) };
+            *rows = Some(ArchetypeRows {
+                column,
+                entities,
+                row: 0,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::StorageType;
+
+    struct A(u32);
+
+    struct B(u32);
+
+    struct C(u32);
+
+    /// Entities whose `A` and `B` were visited, with their values and `C`, after a mix of
+    /// spawns, structural changes and a write through the query.
+    fn visited(storage_type: StorageType) -> Vec<(usize, u32, u32, Option<u32>)> {
+        let mut ecs = Ecs::new();
+        ecs.set_default_storage(storage_type);
+        ecs.register_component::<A>()
+            .expect("Could not register component");
+        ecs.register_component::<B>()
+            .expect("Could not register component");
+        ecs.register_component::<C>()
+            .expect("Could not register component");
+        ecs.register_component::<Unmatched>()
+            .expect("Could not register component");
+
+        let mut entities = Vec::new();
+        for index in 0..12 {
+            let entity = match index % 3 {
+                0 => ecs.spawn((A(index), B(index))),
+                1 => ecs.spawn((A(index),)),
+                _ => ecs.spawn((A(index), B(index), C(index))),
+            };
+            entities.push(entity.expect("Could not spawn entity"));
+        }
+        ecs.remove_component::<B>(entities[3])
+            .expect("Could not remove component");
+        ecs.add_component(entities[4], B(40))
+            .expect("Could not add component");
+        ecs.add_component(entities[6], C(60))
+            .expect("Could not add component");
+        ecs.remove_entity(entities[8])
+            .expect("Could not remove entity");
+
+        for (_, (mut a, _)) in ecs.query::<(&mut A, &B)>().expect("Could not query").iter() {
+            a.0 += 100;
+        }
+
+        let mut visited: Vec<_> = ecs
+            .query_filtered::<(&A, &B, Option<&C>), Without<Unmatched>>()
+            .expect("Could not query")
+            .iter()
+            .map(|(entity, (a, b, c))| (entity.index(), a.0, b.0, c.map(|c| c.0)))
+            .collect();
+        visited.sort();
+
+        visited
+    }
+
+    /// Never added to any entity.
+    struct Unmatched;
+
+    #[test]
+    fn table_queries_visit_the_same_entities_as_dense_ones() {
+        let dense = visited(StorageType::Dense);
+
+        assert_eq!(dense.len(), 7);
+        assert!(dense.contains(&(4, 104, 40, None)));
+        assert!(dense.contains(&(6, 106, 6, Some(60))));
+        assert_eq!(visited(StorageType::Table), dense);
+    }
+}
@@ -0,0 +1,806 @@
+use super::{read_lock, Children, Component, Ecs, EcsError, Entity, Name, Parent};
+use std::{collections::HashMap, fs, io, path::Path};
+
+/// Version written into every save. Loading rejects saves from newer versions, and components
+/// read older ones through [`ValueReader::version`].
+///
+/// 2: rigid bodies store their mass, restitution and friction.
+pub const FORMAT_VERSION: u32 = 2;
+
+const TEXT_HEADER: &str = "goblin_world";
+const BINARY_MAGIC: &[u8; 4] = b"GWLD";
+
+#[derive(Debug)]
+pub enum SerializationError {
+    Io(io::Error),
+    Ecs(EcsError),
+    InvalidHeader,
+    UnsupportedVersion(u32),
+    UnknownComponent(String),
+    UnknownEntity(u32),
+    UnknownAsset(String),
+    InvalidValue(String),
+    UnexpectedEnd,
+}
+
+impl From<io::Error> for SerializationError {
+    fn from(value: io::Error) -> Self {
+        SerializationError::Io(value)
+    }
+}
+
+impl From<EcsError> for SerializationError {
+    fn from(value: EcsError) -> Self {
+        SerializationError::Ecs(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorldFormat {
+    /// Line based and human readable, one entity header followed by one line per component.
+    Text,
+    /// Compact little-endian encoding of the same data.
+    Binary,
+}
+
+/// A component that can be written to and read back from a world save. `NAME` identifies the
+/// component in saves, so it must not change once saves exist.
+pub trait Serializable: Component + Sized {
+    const NAME: &'static str;
+
+    fn save(&self, ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError>;
+
+    fn load(ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError>;
+}
+
+/// Type-erased save and load functions for one registered component type.
+pub(super) struct Serializer {
+    save: fn(&Ecs, usize, &mut ValueWriter) -> Result<bool, SerializationError>,
+    load: fn(&mut Ecs, Entity, &mut ValueReader) -> Result<(), SerializationError>,
+}
+
+fn save_component<T: Serializable>(
+    ecs: &Ecs,
+    index: usize,
+    writer: &mut ValueWriter,
+) -> Result<bool, SerializationError> {
+    let Ok(cell) = ecs.component_cell::<T>() else {
+        return Ok(false);
+    };
+    let storage = read_lock(cell)?;
+    let Some(component) = storage.get(index) else {
+        return Ok(false);
+    };
+    component.save(ecs, writer)?;
+
+    Ok(true)
+}
+
+fn load_component<T: Serializable>(
+    ecs: &mut Ecs,
+    entity: Entity,
+    reader: &mut ValueReader,
+) -> Result<(), SerializationError> {
+    let component = T::load(ecs, reader)?;
+    ecs.add_component(entity, component)?;
+
+    Ok(())
+}
+
+/// Writes the values of one component in the chosen format.
+pub struct ValueWriter<'a> {
+    format: WorldFormat,
+    text: String,
+    bytes: Vec<u8>,
+    entity_ids: &'a HashMap<Entity, u32>,
+}
+
+impl<'a> ValueWriter<'a> {
+    fn new(format: WorldFormat, entity_ids: &'a HashMap<Entity, u32>) -> Self {
+        Self {
+            format,
+            text: String::new(),
+            bytes: Vec::new(),
+            entity_ids,
+        }
+    }
+
+    fn token(&mut self, token: &str) {
+        self.text.push(' ');
+        self.text.push_str(token);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        match self.format {
+            WorldFormat::Text => self.token(if value { "true" } else { "false" }),
+            WorldFormat::Binary => self.bytes.push(value as u8),
+        }
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        match self.format {
+            WorldFormat::Text => self.token(&value.to_string()),
+            WorldFormat::Binary => self.bytes.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        match self.format {
+            // Debug formatting round-trips exactly and always includes a decimal point.
+            WorldFormat::Text => self.token(&format!("{:?}", value)),
+            WorldFormat::Binary => self.bytes.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+
+    pub fn string(&mut self, value: &str) {
+        match self.format {
+            WorldFormat::Text => self.token(&format!("{:?}", value)),
+            WorldFormat::Binary => {
+                self.bytes
+                    .extend_from_slice(&(value.len() as u32).to_le_bytes());
+                self.bytes.extend_from_slice(value.as_bytes());
+            }
+        }
+    }
+
+    /// Writes a reference to another entity. It is remapped to the matching new entity when the
+    /// save is loaded.
+    pub fn entity(&mut self, entity: Entity) -> Result<(), SerializationError> {
+        let id = *self
+            .entity_ids
+            .get(&entity)
+            .ok_or(SerializationError::Ecs(EcsError::StaleEntity))?;
+        match self.format {
+            WorldFormat::Text => self.token(&format!("@{}", id)),
+            WorldFormat::Binary => self.bytes.extend_from_slice(&id.to_le_bytes()),
+        }
+
+        Ok(())
+    }
+}
+
+enum Payload {
+    Text(Vec<String>),
+    Binary(Vec<u8>),
+}
+
+/// Reads the values of one component back in the order they were written.
+pub struct ValueReader<'a> {
+    payload: &'a Payload,
+    position: usize,
+    version: u32,
+    entities: &'a HashMap<u32, Entity>,
+}
+
+impl<'a> ValueReader<'a> {
+    /// The [`FORMAT_VERSION`] the save was written with, for components whose layout changed.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn token(&mut self) -> Result<&'a str, SerializationError> {
+        let Payload::Text(tokens) = self.payload else {
+            return Err(SerializationError::InvalidValue(
+                "expected text payload".to_string(),
+            ));
+        };
+        let token = tokens
+            .get(self.position)
+            .ok_or(SerializationError::UnexpectedEnd)?;
+        self.position += 1;
+
+        Ok(token)
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], SerializationError> {
+        let Payload::Binary(bytes) = self.payload else {
+            return Err(SerializationError::InvalidValue(
+                "expected binary payload".to_string(),
+            ));
+        };
+        let slice = bytes
+            .get(self.position..self.position + N)
+            .ok_or(SerializationError::UnexpectedEnd)?;
+        self.position += N;
+
+        Ok(slice.try_into().expect("Slice has the requested length"))
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self) -> Result<T, SerializationError> {
+        let token = self.token()?;
+
+        token
+            .parse()
+            .map_err(|_| SerializationError::InvalidValue(token.to_string()))
+    }
+
+    pub fn bool(&mut self) -> Result<bool, SerializationError> {
+        match self.payload {
+            Payload::Text(_) => self.parse(),
+            Payload::Binary(_) => match self.bytes::<1>()? {
+                [0] => Ok(false),
+                [1] => Ok(true),
+                [other] => Err(SerializationError::InvalidValue(other.to_string())),
+            },
+        }
+    }
+
+    pub fn u32(&mut self) -> Result<u32, SerializationError> {
+        match self.payload {
+            Payload::Text(_) => self.parse(),
+            Payload::Binary(_) => Ok(u32::from_le_bytes(self.bytes()?)),
+        }
+    }
+
+    pub fn f32(&mut self) -> Result<f32, SerializationError> {
+        match self.payload {
+            Payload::Text(_) => self.parse(),
+            Payload::Binary(_) => Ok(f32::from_le_bytes(self.bytes()?)),
+        }
+    }
+
+    pub fn string(&mut self) -> Result<String, SerializationError> {
+        match self.payload {
+            Payload::Text(_) => {
+                let token = self.token()?;
+                unquote(token).ok_or_else(|| SerializationError::InvalidValue(token.to_string()))
+            }
+            Payload::Binary(bytes) => {
+                let len = u32::from_le_bytes(self.bytes()?) as usize;
+                let slice = bytes
+                    .get(self.position..self.position + len)
+                    .ok_or(SerializationError::UnexpectedEnd)?;
+                self.position += len;
+
+                String::from_utf8(slice.to_vec())
+                    .map_err(|error| SerializationError::InvalidValue(error.to_string()))
+            }
+        }
+    }
+
+    /// Reads an entity reference, already remapped to the entity created by this load.
+    pub fn entity(&mut self) -> Result<Entity, SerializationError> {
+        let id = match self.payload {
+            Payload::Text(_) => {
+                let token = self.token()?;
+                token
+                    .strip_prefix('@')
+                    .and_then(|id| id.parse().ok())
+                    .ok_or_else(|| SerializationError::InvalidValue(token.to_string()))?
+            }
+            Payload::Binary(_) => u32::from_le_bytes(self.bytes()?),
+        };
+
+        self.entities
+            .get(&id)
+            .copied()
+            .ok_or(SerializationError::UnknownEntity(id))
+    }
+}
+
+/// Splits a line into whitespace separated tokens, keeping quoted strings together.
+fn tokenize(line: &str) -> Result<Vec<String>, SerializationError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut token = String::new();
+        if c == '"' {
+            token.push(chars.next().expect("Peeked character"));
+            let mut escaped = false;
+            loop {
+                let c = chars
+                    .next()
+                    .ok_or_else(|| SerializationError::InvalidValue(line.to_string()))?;
+                token.push(c);
+                match c {
+                    '\\' if !escaped => escaped = true,
+                    '"' if !escaped => break,
+                    _ => escaped = false,
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn unquote(token: &str) -> Option<String> {
+    let inner = token.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            '0' => value.push('\0'),
+            'u' => {
+                let hex: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+            other => value.push(other),
+        }
+    }
+
+    Some(value)
+}
+
+/// Saved entities by their id in the save, each with its named component payloads.
+type SavedEntities = Vec<(u32, Vec<(String, Payload)>)>;
+
+/// The format version of a save and its entities.
+type SavedWorld = (u32, SavedEntities);
+
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SerializationError> {
+        let slice = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or(SerializationError::UnexpectedEnd)?;
+        self.position += len;
+
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, SerializationError> {
+        let bytes = self.take(4)?;
+
+        Ok(u32::from_le_bytes(
+            bytes.try_into().expect("Slice has the requested length"),
+        ))
+    }
+}
+
+fn check_version(version: u32) -> Result<u32, SerializationError> {
+    if version > FORMAT_VERSION {
+        return Err(SerializationError::UnsupportedVersion(version));
+    }
+
+    Ok(version)
+}
+
+fn parse_text(data: &str) -> Result<SavedWorld, SerializationError> {
+    let mut lines = data.lines().map(str::trim).filter(|line| !line.is_empty());
+
+    let header = tokenize(lines.next().ok_or(SerializationError::InvalidHeader)?)?;
+    let version = match header.as_slice() {
+        [name, version] if name == TEXT_HEADER => check_version(
+            version
+                .parse()
+                .map_err(|_| SerializationError::InvalidHeader)?,
+        )?,
+        _ => return Err(SerializationError::InvalidHeader),
+    };
+
+    let mut entities: SavedEntities = Vec::new();
+    for line in lines {
+        let mut tokens = tokenize(line)?;
+        let name = tokens.remove(0);
+
+        if name == "entity" {
+            let id = tokens
+                .first()
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| SerializationError::InvalidValue(line.to_string()))?;
+            entities.push((id, Vec::new()));
+            continue;
+        }
+
+        let (_, components) = entities
+            .last_mut()
+            .ok_or_else(|| SerializationError::InvalidValue(line.to_string()))?;
+        components.push((name, Payload::Text(tokens)));
+    }
+
+    Ok((version, entities))
+}
+
+fn parse_binary(data: &[u8]) -> Result<SavedWorld, SerializationError> {
+    let mut reader = BinaryReader {
+        bytes: data,
+        position: 0,
+    };
+    if reader.take(BINARY_MAGIC.len())? != BINARY_MAGIC {
+        return Err(SerializationError::InvalidHeader);
+    }
+    let version = check_version(reader.u32()?)?;
+
+    let entity_count = reader.u32()?;
+    let mut entities = Vec::new();
+    for _ in 0..entity_count {
+        let id = reader.u32()?;
+        let component_count = reader.u32()?;
+
+        let mut components = Vec::new();
+        for _ in 0..component_count {
+            let name_len = reader.u32()? as usize;
+            let name = String::from_utf8(reader.take(name_len)?.to_vec())
+                .map_err(|error| SerializationError::InvalidValue(error.to_string()))?;
+            let payload_len = reader.u32()? as usize;
+            let payload = reader.take(payload_len)?.to_vec();
+
+            components.push((name, Payload::Binary(payload)));
+        }
+        entities.push((id, components));
+    }
+
+    Ok((version, entities))
+}
+
+impl Ecs {
+    /// Opts a component type into world saves.
+    pub fn register_serializable<T: Serializable>(&mut self) {
+        self.serializers.insert(
+            T::NAME,
+            Serializer {
+                save: save_component::<T>,
+                load: load_component::<T>,
+            },
+        );
+    }
+
+    /// Writes every living entity with its serializable components. Components that were not
+    /// registered through `register_serializable` are left out.
+    pub fn save_world(&self, format: WorldFormat) -> Result<Vec<u8>, SerializationError> {
+        let entities: Vec<Entity> = self
+            .entities
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.alive)
+            .map(|(index, slot)| Entity {
+                index,
+                generation: slot.generation,
+            })
+            .collect();
+        let entity_ids: HashMap<Entity, u32> = entities
+            .iter()
+            .enumerate()
+            .map(|(id, entity)| (*entity, id as u32))
+            .collect();
+
+        let mut text = format!("{} {}\n", TEXT_HEADER, FORMAT_VERSION);
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(entities.len() as u32).to_le_bytes());
+
+        for (id, entity) in entities.iter().enumerate() {
+            let mut components = Vec::new();
+            for (name, serializer) in self.serializers.iter() {
+                let mut writer = ValueWriter::new(format, &entity_ids);
+                if (serializer.save)(self, entity.index, &mut writer)? {
+                    components.push((*name, writer));
+                }
+            }
+
+            match format {
+                WorldFormat::Text => {
+                    text.push_str(&format!("entity {}\n", id));
+                    for (name, writer) in components {
+                        text.push_str(&format!("{}{}\n", name, writer.text));
+                    }
+                }
+                WorldFormat::Binary => {
+                    bytes.extend_from_slice(&(id as u32).to_le_bytes());
+                    bytes.extend_from_slice(&(components.len() as u32).to_le_bytes());
+                    for (name, writer) in components {
+                        bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+                        bytes.extend_from_slice(name.as_bytes());
+                        bytes.extend_from_slice(&(writer.bytes.len() as u32).to_le_bytes());
+                        bytes.extend_from_slice(&writer.bytes);
+                    }
+                }
+            }
+        }
+
+        Ok(match format {
+            WorldFormat::Text => text.into_bytes(),
+            WorldFormat::Binary => bytes,
+        })
+    }
+
+    /// Spawns every entity of a save made by `save_world`, in either format, next to the
+    /// entities already in the world. Entity references are remapped to the new entities,
+    /// which are returned in save order.
+    pub fn load_world(&mut self, data: &[u8]) -> Result<Vec<Entity>, SerializationError> {
+        let (version, saved) = if data.starts_with(BINARY_MAGIC) {
+            parse_binary(data)?
+        } else {
+            let text = std::str::from_utf8(data).map_err(|_| SerializationError::InvalidHeader)?;
+            parse_text(text)?
+        };
+
+        // Every entity has to exist before components referencing it are loaded.
+        let mut entities = HashMap::new();
+        let mut created = Vec::new();
+        for (id, _) in saved.iter() {
+            let entity = self.create_entity()?;
+            entities.insert(*id, entity);
+            created.push(entity);
+        }
+
+        let result = saved.iter().try_for_each(|(id, components)| {
+            let entity = entities[id];
+            components.iter().try_for_each(|(name, payload)| {
+                let load = self
+                    .serializers
+                    .get(name.as_str())
+                    .ok_or_else(|| SerializationError::UnknownComponent(name.clone()))?
+                    .load;
+                let mut reader = ValueReader {
+                    payload,
+                    position: 0,
+                    version,
+                    entities: &entities,
+                };
+
+                load(self, entity, &mut reader)
+            })
+        });
+
+        // A broken save should not leave half loaded entities behind.
+        if let Err(error) = result {
+            for entity in created {
+                if self.is_alive(entity) {
+                    self.remove_entity(entity)?;
+                }
+            }
+            return Err(error);
+        }
+
+        Ok(created)
+    }
+
+    pub fn save_world_to_file(
+        &self,
+        path: &Path,
+        format: WorldFormat,
+    ) -> Result<(), SerializationError> {
+        fs::write(path, self.save_world(format)?)?;
+
+        Ok(())
+    }
+
+    pub fn load_world_from_file(&mut self, path: &Path) -> Result<Vec<Entity>, SerializationError> {
+        let data = fs::read(path)?;
+
+        self.load_world(&data)
+    }
+}
+
+impl Serializable for Parent {
+    const NAME: &'static str = "Parent";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        writer.entity(self.get())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        Ok(Parent::new(reader.entity()?))
+    }
+}
+
+impl Serializable for Children {
+    const NAME: &'static str = "Children";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        writer.u32(self.len() as u32);
+        self.entities()
+            .iter()
+            .try_for_each(|child| writer.entity(*child))
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        let len = reader.u32()?;
+        let children = (0..len)
+            .map(|_| reader.entity())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Children::new(children))
+    }
+}
+
+impl Serializable for Name {
+    const NAME: &'static str = "Name";
+
+    fn save(&self, _ecs: &Ecs, writer: &mut ValueWriter) -> Result<(), SerializationError> {
+        writer.string(self.as_str());
+
+        Ok(())
+    }
+
+    fn load(_ecs: &Ecs, reader: &mut ValueReader) -> Result<Self, SerializationError> {
+        Ok(Name::new(reader.string()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::mesh::MeshComponent, mesh_manager::MeshManager};
+
+    /// A root with two children, one of which has a child of its own.
+    fn hierarchy() -> (Ecs, Vec<Entity>) {
+        let mut ecs = Ecs::new();
+        let entities: Vec<Entity> = ["root", "left", "right", "leaf"]
+            .into_iter()
+            .map(|name| {
+                ecs.spawn((Name::new(name),))
+                    .expect("Could not spawn entity")
+            })
+            .collect();
+        ecs.set_parent(entities[1], entities[0])
+            .expect("Could not set parent");
+        ecs.set_parent(entities[2], entities[0])
+            .expect("Could not set parent");
+        ecs.set_parent(entities[3], entities[1])
+            .expect("Could not set parent");
+
+        (ecs, entities)
+    }
+
+    /// A world whose entity indices are already taken, so loaded entities can't keep theirs.
+    fn occupied_world() -> Ecs {
+        let (mut ecs, entities) = hierarchy();
+        ecs.remove_entity(entities[2])
+            .expect("Could not remove entity");
+        ecs.create_entity().expect("Could not create entity");
+
+        ecs
+    }
+
+    fn parent(ecs: &mut Ecs, entity: Entity) -> Option<Entity> {
+        ecs.get_component::<Parent>(entity)
+            .expect("Could not get parent")
+            .map(|parent| parent.get())
+    }
+
+    fn children(ecs: &mut Ecs, entity: Entity) -> Vec<Entity> {
+        ecs.get_component::<Children>(entity)
+            .expect("Could not get children")
+            .map(|children| children.entities().to_vec())
+            .unwrap_or_default()
+    }
+
+    fn replace(data: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+        let start = data
+            .windows(from.len())
+            .position(|window| window == from)
+            .expect("Missing bytes to replace");
+        [&data[..start], to, &data[start + from.len()..]].concat()
+    }
+
+    #[test]
+    fn hierarchies_are_remapped_into_non_empty_worlds() {
+        for format in [WorldFormat::Text, WorldFormat::Binary] {
+            let (saved, original) = hierarchy();
+            let data = saved.save_world(format).expect("Could not save world");
+
+            let mut ecs = occupied_world();
+            let existing = ecs.entity_count();
+            let loaded = ecs.load_world(&data).expect("Could not load world");
+            assert_eq!(loaded.len(), 4);
+            assert_eq!(ecs.entity_count(), existing + 4);
+            assert_ne!(loaded, original);
+
+            assert_eq!(parent(&mut ecs, loaded[0]), None);
+            assert_eq!(parent(&mut ecs, loaded[1]), Some(loaded[0]));
+            assert_eq!(parent(&mut ecs, loaded[2]), Some(loaded[0]));
+            assert_eq!(parent(&mut ecs, loaded[3]), Some(loaded[1]));
+            assert_eq!(children(&mut ecs, loaded[0]), vec![loaded[1], loaded[2]]);
+            assert_eq!(children(&mut ecs, loaded[1]), vec![loaded[3]]);
+            assert!(children(&mut ecs, loaded[3]).is_empty());
+
+            // The hierarchy that was already there is left alone.
+            assert_eq!(children(&mut ecs, original[0]), vec![original[1]]);
+            assert_eq!(children(&mut ecs, original[1]), vec![original[3]]);
+        }
+    }
+
+    fn mesh_world(names: &[&str]) -> Ecs {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<MeshComponent>()
+            .expect("Could not register component");
+        ecs.register_serializable::<MeshComponent>();
+        let mut mesh_manager = MeshManager::new();
+        for name in names {
+            mesh_manager.reserve_mesh(name);
+        }
+        ecs.insert_resource(mesh_manager);
+
+        ecs
+    }
+
+    #[test]
+    fn meshes_are_saved_by_name() {
+        for format in [WorldFormat::Text, WorldFormat::Binary] {
+            let mut saved = mesh_world(&["plane", "cube"]);
+            let cube = saved
+                .resource::<MeshManager>()
+                .expect("Could not get mesh manager")
+                .mesh_id("cube")
+                .expect("Missing cube mesh");
+            saved
+                .spawn((MeshComponent { id: cube },))
+                .expect("Could not spawn entity");
+            let data = saved.save_world(format).expect("Could not save world");
+
+            // Ids follow the order meshes were added, which differs between the two worlds.
+            let mut ecs = mesh_world(&["cube", "plane"]);
+            let loaded = ecs.load_world(&data).expect("Could not load world");
+            let mesh = ecs
+                .get_component::<MeshComponent>(loaded[0])
+                .expect("Could not get mesh")
+                .expect("Missing mesh");
+            assert_eq!(mesh.id, 0);
+            assert_ne!(mesh.id, cube);
+
+            let mut ecs = mesh_world(&["plane"]);
+            assert!(matches!(
+                ecs.load_world(&data),
+                Err(SerializationError::UnknownAsset(name)) if name == "cube"
+            ));
+            assert_eq!(ecs.entity_count(), 0);
+        }
+
+        let mut unnamed = mesh_world(&[]);
+        unnamed
+            .spawn((MeshComponent { id: 3 },))
+            .expect("Could not spawn entity");
+        assert!(matches!(
+            unnamed.save_world(WorldFormat::Text),
+            Err(SerializationError::UnknownAsset(_))
+        ));
+    }
+
+    #[test]
+    fn corrupt_saves_leave_the_world_unchanged() {
+        for format in [WorldFormat::Text, WorldFormat::Binary] {
+            let (saved, _) = hierarchy();
+            let data = saved.save_world(format).expect("Could not save world");
+            // The roots load fine before a child's parent turns out to be unknown.
+            let unknown_component = replace(&data, b"Parent", b"Porent");
+            let truncated = &data[..data.len() - 3];
+
+            let mut ecs = occupied_world();
+            let before = ecs.save_world(format).expect("Could not save world");
+            let existing = ecs.entity_count();
+
+            assert!(matches!(
+                ecs.load_world(&unknown_component),
+                Err(SerializationError::UnknownComponent(name)) if name == "Porent"
+            ));
+            assert!(ecs.load_world(truncated).is_err());
+
+            assert_eq!(ecs.entity_count(), existing);
+            assert_eq!(
+                ecs.save_world(format).expect("Could not save world"),
+                before
+            );
+        }
+    }
+}
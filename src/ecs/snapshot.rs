@@ -0,0 +1,457 @@
+use super::{hooks::HookKind, read_lock, Component, Ecs, EcsError, Entity, EntitySlot};
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// A component that can be copied into a [`Snapshot`] and compared against another one.
+pub trait Snapshottable: Component + Clone + PartialEq + fmt::Debug {}
+
+impl<T: Component + Clone + PartialEq + fmt::Debug> Snapshottable for T {}
+
+/// Copies the values of one registered component type out of a world.
+pub(super) type Snapshotter = fn(&Ecs) -> Result<Box<dyn ComponentSnapshot>, EcsError>;
+
+/// Type-erased values of one component type, sorted by entity index.
+pub(super) trait ComponentSnapshot: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn name(&self) -> &'static str;
+    fn restore(&self, ecs: &mut Ecs) -> Result<(), EcsError>;
+    fn diff(&self, other: &dyn ComponentSnapshot, changes: &mut Vec<ComponentChange>);
+}
+
+struct Column<T> {
+    values: Vec<(Entity, T)>,
+}
+
+fn take_column<T: Snapshottable>(ecs: &Ecs) -> Result<Box<dyn ComponentSnapshot>, EcsError> {
+    let storage = read_lock(ecs.component_cell::<T>()?)?;
+    let mut values: Vec<(Entity, T)> = storage
+        .iter()
+        .map(|(index, component)| (ecs.entity_at(index), component.clone()))
+        .collect();
+    values.sort_by_key(|(entity, _)| entity.index);
+
+    Ok(Box::new(Column { values }))
+}
+
+impl<T: Snapshottable> Column<T> {
+    fn get(&self, entity: Entity) -> Option<&T> {
+        self.values
+            .binary_search_by_key(&entity.index, |(other, _)| other.index)
+            .ok()
+            .map(|position| &self.values[position])
+            .filter(|(other, _)| *other == entity)
+            .map(|(_, component)| component)
+    }
+}
+
+impl<T: Snapshottable> ComponentSnapshot for Column<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &'static str {
+        type_name::<T>()
+    }
+
+    /// Only writes values that differ, so untouched components are not flagged as changed.
+    /// Components that go or come back run their remove or add hooks, overwritten ones their
+    /// replace hooks.
+    fn restore(&self, ecs: &mut Ecs) -> Result<(), EcsError> {
+        let type_id = TypeId::of::<T>();
+        let stale: Vec<Entity> = read_lock(ecs.component_cell::<T>()?)?
+            .iter()
+            .map(|(index, _)| ecs.entity_at(index))
+            .filter(|entity| self.get(*entity).is_none())
+            .collect();
+        for entity in stale {
+            ecs.run_hooks(type_id, HookKind::Remove, entity)?;
+            ecs.take_component::<T>(entity)?;
+        }
+
+        for (entity, component) in self.values.iter() {
+            let current = ecs.component_vec_mut::<T>()?.get(entity.index);
+            if current != Some(component) {
+                let kind = if ecs.insert_component(*entity, component.clone())? {
+                    HookKind::Replace
+                } else {
+                    HookKind::Add
+                };
+                ecs.run_hooks(type_id, kind, *entity)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn diff(&self, other: &dyn ComponentSnapshot, changes: &mut Vec<ComponentChange>) {
+        let Some(other) = other.as_any().downcast_ref::<Column<T>>() else {
+            return;
+        };
+        let component = self.name();
+
+        for (entity, before) in self.values.iter() {
+            let kind = match other.get(*entity) {
+                None => ChangeKind::Removed(format!("{:?}", before)),
+                Some(after) if after != before => ChangeKind::Changed {
+                    before: format!("{:?}", before),
+                    after: format!("{:?}", after),
+                },
+                Some(_) => continue,
+            };
+            changes.push(ComponentChange {
+                entity: *entity,
+                component,
+                kind,
+            });
+        }
+
+        for (entity, after) in other.values.iter() {
+            if self.get(*entity).is_none() {
+                changes.push(ComponentChange {
+                    entity: *entity,
+                    component,
+                    kind: ChangeKind::Added(format!("{:?}", after)),
+                });
+            }
+        }
+    }
+}
+
+/// Copy of a world's entities and of every component registered through
+/// [`Ecs::register_snapshot`], taken by [`Ecs::snapshot`].
+pub struct Snapshot {
+    entities: Vec<EntitySlot>,
+    free_entities: Vec<usize>,
+    components: HashMap<TypeId, Box<dyn ComponentSnapshot>>,
+}
+
+impl Snapshot {
+    fn alive(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.alive)
+            .map(|(index, slot)| Entity {
+                index,
+                generation: slot.generation,
+            })
+    }
+
+    fn is_alive(&self, entity: Entity) -> bool {
+        self.entities
+            .get(entity.index)
+            .is_some_and(|slot| slot.alive && slot.generation == entity.generation)
+    }
+
+    /// What changed going from this snapshot to `other`. Only components captured by both are
+    /// compared.
+    pub fn diff(&self, other: &Snapshot) -> WorldDiff {
+        let added = other.alive().filter(|entity| !self.is_alive(*entity));
+        let removed = self.alive().filter(|entity| !other.is_alive(*entity));
+
+        let mut components = Vec::new();
+        for (type_id, column) in self.components.iter() {
+            if let Some(other_column) = other.components.get(type_id) {
+                column.diff(other_column.as_ref(), &mut components);
+            }
+        }
+        components.sort_by_key(|change| {
+            (
+                change.entity.index,
+                change.entity.generation,
+                change.component,
+            )
+        });
+
+        WorldDiff {
+            added: added.collect(),
+            removed: removed.collect(),
+            components,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChangeKind {
+    Added(String),
+    Removed(String),
+    Changed { before: String, after: String },
+}
+
+/// One component that differs between two snapshots, with its values in `Debug` form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentChange {
+    pub entity: Entity,
+    pub component: &'static str,
+    pub kind: ChangeKind,
+}
+
+/// Structural and value differences between two snapshots, see [`Snapshot::diff`]. Prints one
+/// line per difference, so `assert!(diff.is_empty(), "{}", diff)` shows exactly what changed.
+#[derive(Clone, Default, PartialEq)]
+pub struct WorldDiff {
+    pub added: Vec<Entity>,
+    pub removed: Vec<Entity>,
+    pub components: Vec<ComponentChange>,
+}
+
+impl WorldDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.components.is_empty()
+    }
+}
+
+/// `Type` for `some::path::Type`, applied to every path inside generics as well.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut segment = String::new();
+
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+            continue;
+        }
+        short.push_str(segment.rsplit("::").next().unwrap_or_default());
+        segment.clear();
+        short.push(c);
+    }
+    short.push_str(segment.rsplit("::").next().unwrap_or_default());
+
+    short
+}
+
+impl fmt::Display for WorldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+
+        for entity in self.added.iter() {
+            writeln!(f, "+ entity {}v{}", entity.index, entity.generation)?;
+        }
+        for entity in self.removed.iter() {
+            writeln!(f, "- entity {}v{}", entity.index, entity.generation)?;
+        }
+        for change in self.components.iter() {
+            let entity = change.entity;
+            let component = short_type_name(change.component);
+            match &change.kind {
+                ChangeKind::Added(value) => writeln!(
+                    f,
+                    "+ {}v{} {}: {}",
+                    entity.index, entity.generation, component, value
+                )?,
+                ChangeKind::Removed(value) => writeln!(
+                    f,
+                    "- {}v{} {}: {}",
+                    entity.index, entity.generation, component, value
+                )?,
+                ChangeKind::Changed { before, after } => writeln!(
+                    f,
+                    "~ {}v{} {}: {} -> {}",
+                    entity.index, entity.generation, component, before, after
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Same as `Display`, so failing `assert_eq!`s on diffs stay readable.
+impl fmt::Debug for WorldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Ecs {
+    /// Opts a component type into snapshots. Snapshots only copy and restore registered types.
+    pub fn register_snapshot<T: Snapshottable>(&mut self) {
+        self.snapshotters
+            .insert(TypeId::of::<T>(), take_column::<T>);
+    }
+
+    /// Copies every entity and every registered component. Fails if one of them is borrowed
+    /// mutably.
+    pub fn snapshot(&self) -> Result<Snapshot, EcsError> {
+        let components = self
+            .snapshotters
+            .iter()
+            .filter(|(type_id, _)| self.component_vecs.contains_key(type_id))
+            .map(|(type_id, take)| Ok((*type_id, take(self)?)))
+            .collect::<Result<_, EcsError>>()?;
+
+        Ok(Snapshot {
+            entities: self.entities.clone(),
+            free_entities: self.free_entities.clone(),
+            components,
+        })
+    }
+
+    /// Rolls the world back to `snapshot`. Entities come back with the handles they had, and
+    /// registered components get their captured values back. Components that were not
+    /// captured are kept on entities alive in both, and lost on entities brought back. Only
+    /// values that differ are written back, and only those count as changed.
+    ///
+    /// Hooks run as if the differences were made one by one: remove hooks for every component
+    /// of dropped entities and for captured components that go, add and replace hooks for
+    /// captured values written back. Commands they queue, such as the ones keeping the
+    /// `Collider` in sync, apply with the next `apply_commands`.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), EcsError> {
+        self.flush_reserved()?;
+
+        // Drop entities the snapshot does not know about, including reused slots.
+        let stale: Vec<Entity> = (0..self.entities.len())
+            .filter(|&index| self.entities[index].alive)
+            .map(|index| self.entity_at(index))
+            .filter(|entity| !snapshot.is_alive(*entity))
+            .collect();
+        for entity in stale {
+            self.run_remove_hooks(entity)?;
+            self.clear_entity(entity);
+        }
+
+        while self.entities.len() < snapshot.entities.len() {
+            for component_vec in self.component_vecs.values_mut() {
+                component_vec.push_none();
+            }
+            self.entities.push(EntitySlot {
+                generation: 0,
+                alive: false,
+            });
+        }
+        let mut free_entities = snapshot.free_entities.clone();
+        for index in snapshot.entities.len()..self.entities.len() {
+            let slot = &mut self.entities[index];
+            if slot.alive {
+                slot.alive = false;
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+            free_entities.push(index);
+        }
+        self.entities[..snapshot.entities.len()].copy_from_slice(&snapshot.entities);
+        self.free_entities = free_entities;
+
+        // Sorted so restoring is deterministic.
+        let columns: BTreeMap<&'static str, &dyn ComponentSnapshot> = snapshot
+            .components
+            .values()
+            .map(|column| (column.name(), column.as_ref()))
+            .collect();
+        columns
+            .into_values()
+            .try_for_each(|column| column.restore(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    /// Not captured by snapshots.
+    struct Solid;
+
+    /// What the hooks saw, in order.
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    fn world() -> Ecs {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<Health>()
+            .expect("Could not register component");
+        ecs.register_component::<Solid>()
+            .expect("Could not register component");
+        ecs.register_snapshot::<Health>();
+        ecs.insert_resource(Log::default());
+
+        ecs
+    }
+
+    fn log(ecs: &Ecs, line: String) -> Result<(), EcsError> {
+        ecs.resource_mut::<Log>()?.0.push(line);
+        Ok(())
+    }
+
+    fn spawn(ecs: &mut Ecs, health: Option<u32>) -> Entity {
+        let entity = ecs.create_entity().expect("Could not create entity");
+        if let Some(health) = health {
+            ecs.add_component(entity, Health(health))
+                .expect("Could not add component");
+        }
+
+        entity
+    }
+
+    #[test]
+    fn diff_prints_one_line_per_difference() {
+        let mut ecs = world();
+        let kept = spawn(&mut ecs, Some(10));
+        let removed = spawn(&mut ecs, Some(20));
+        let stripped = spawn(&mut ecs, Some(30));
+        let before = ecs.snapshot().expect("Could not take snapshot");
+
+        ecs.add_component(kept, Health(5))
+            .expect("Could not add component");
+        ecs.remove_entity(removed).expect("Could not remove entity");
+        ecs.remove_component::<Health>(stripped)
+            .expect("Could not remove component");
+        spawn(&mut ecs, Some(40));
+        let after = ecs.snapshot().expect("Could not take snapshot");
+
+        assert_eq!(
+            before.diff(&after).to_string(),
+            "+ entity 1v1\n\
+             - entity 1v0\n\
+             ~ 0v0 Health: Health(10) -> Health(5)\n\
+             - 1v0 Health: Health(20)\n\
+             + 1v1 Health: Health(40)\n\
+             - 2v0 Health: Health(30)\n"
+        );
+        assert_eq!(after.diff(&after).to_string(), "no differences\n");
+    }
+
+    #[test]
+    fn restore_runs_hooks_for_structural_differences() {
+        let mut ecs = world();
+        ecs.on_add::<Health>(|ecs, entity| log(ecs, format!("add {}", entity.index())));
+        ecs.on_replace::<Health>(|ecs, entity| log(ecs, format!("replace {}", entity.index())));
+        ecs.on_remove::<Health>(|ecs, entity| log(ecs, format!("remove {}", entity.index())));
+        ecs.on_remove::<Solid>(|ecs, entity| log(ecs, format!("unsolid {}", entity.index())));
+
+        let changed = spawn(&mut ecs, Some(10));
+        let stripped = spawn(&mut ecs, Some(20));
+        let untouched = spawn(&mut ecs, Some(30));
+        let snapshot = ecs.snapshot().expect("Could not take snapshot");
+
+        ecs.add_component(changed, Health(15))
+            .expect("Could not add component");
+        ecs.remove_component::<Health>(stripped)
+            .expect("Could not remove component");
+        let spawned = spawn(&mut ecs, Some(40));
+        ecs.add_component(spawned, Solid)
+            .expect("Could not add component");
+        ecs.resource_mut::<Log>()
+            .expect("Could not get log")
+            .0
+            .clear();
+
+        ecs.restore(&snapshot).expect("Could not restore snapshot");
+
+        // Removal hooks of one entity run in no particular order.
+        let mut lines = ecs.resource::<Log>().expect("Could not get log").0.clone();
+        lines.sort();
+        assert_eq!(lines, ["add 1", "remove 3", "replace 0", "unsolid 3"]);
+        assert!(!ecs.is_alive(spawned));
+        assert!(ecs.is_alive(untouched));
+        assert!(snapshot
+            .diff(&ecs.snapshot().expect("Could not take snapshot"))
+            .is_empty());
+    }
+}
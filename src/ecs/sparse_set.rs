@@ -0,0 +1,102 @@
+use std::mem::size_of;
+
+const EMPTY: u32 = u32::MAX;
+
+/// Packs components densely and maps entity indices to them through a sparse lookup table that
+/// only grows as far as the highest entity index actually stored.
+pub struct SparseSet<T> {
+    sparse: Vec<u32>,
+    dense: Vec<T>,
+    entities: Vec<usize>,
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    fn dense_index(&self, index: usize) -> Option<usize> {
+        match self.sparse.get(index) {
+            Some(&dense_index) if dense_index != EMPTY => Some(dense_index as usize),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let dense_index = self.dense_index(index)?;
+
+        self.dense.get(dense_index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let dense_index = self.dense_index(index)?;
+
+        self.dense.get_mut(dense_index)
+    }
+
+    pub fn insert(&mut self, index: usize, value: T) {
+        if let Some(dense_index) = self.dense_index(index) {
+            self.dense[dense_index] = value;
+            return;
+        }
+
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, EMPTY);
+        }
+        self.sparse[index] = self.dense.len() as u32;
+        self.dense.push(value);
+        self.entities.push(index);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let dense_index = self.dense_index(index)?;
+
+        // Move the last element into the hole and repoint its entity at the new location.
+        let value = self.dense.swap_remove(dense_index);
+        self.entities.swap_remove(dense_index);
+        if let Some(&moved_index) = self.entities.get(dense_index) {
+            self.sparse[moved_index] = dense_index as u32;
+        }
+        self.sparse[index] = EMPTY;
+
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    /// Bytes allocated for the packed values and both index tables.
+    pub fn memory_usage(&self) -> usize {
+        self.sparse.capacity() * size_of::<u32>()
+            + self.dense.capacity() * size_of::<T>()
+            + self.entities.capacity() * size_of::<usize>()
+    }
+
+    /// Entity indices in the same order as the packed components.
+    pub fn entities(&self) -> &[usize] {
+        &self.entities
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.entities.iter().copied().zip(self.dense.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.entities.iter().copied().zip(self.dense.iter_mut())
+    }
+}
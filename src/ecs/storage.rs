@@ -0,0 +1,302 @@
+use super::{
+    archetype::ArchetypeId, change_detection::ComponentTicks, sparse_set::SparseSet, table::Table,
+    tag_set::TagSet,
+};
+use std::{mem::size_of, ptr::NonNull};
+
+/// How a component type is laid out in memory, chosen when it is registered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageType {
+    /// One slot per entity. Best for components most entities have.
+    Dense,
+    /// Packed values plus an index lookup. Best for components few entities have.
+    SparseSet,
+    /// Packed values grouped by archetype, so entities with the same table stored components
+    /// line up row by row. Queries only visit the archetypes that can match and walk their
+    /// columns side by side, but adding or removing components moves the entity's other table
+    /// stored components.
+    Table,
+    /// One bit per entity index, for zero-sized tag components. Tags have no change ticks, so
+    /// `Added`, `Changed` and `&mut` queries never match them.
+    Tag,
+}
+
+/// A stored component together with the ticks it was added and last changed at.
+pub struct Tracked<T> {
+    pub(super) component: T,
+    pub(super) ticks: ComponentTicks,
+}
+
+pub enum ComponentStorage<T> {
+    Dense(Vec<Option<Tracked<T>>>),
+    SparseSet(SparseSet<Tracked<T>>),
+    Table(Table<Tracked<T>>),
+    Tag(TagSet),
+}
+
+impl<T> ComponentStorage<T> {
+    /// Storage for a world with `entity_count` entity slots. Dense storage reserves room for
+    /// `capacity` slots up front.
+    pub fn new(storage_type: StorageType, entity_count: usize, capacity: usize) -> Self {
+        match storage_type {
+            StorageType::Dense => {
+                let mut components = Vec::with_capacity(capacity.max(entity_count));
+                components.resize_with(entity_count, || None);
+
+                ComponentStorage::Dense(components)
+            }
+            StorageType::SparseSet => ComponentStorage::SparseSet(SparseSet::new()),
+            StorageType::Table => ComponentStorage::Table(Table::new()),
+            StorageType::Tag => {
+                assert_eq!(size_of::<T>(), 0, "Only zero-sized components can be tags");
+                ComponentStorage::Tag(TagSet::new())
+            }
+        }
+    }
+
+    pub fn storage_type(&self) -> StorageType {
+        match self {
+            ComponentStorage::Dense(_) => StorageType::Dense,
+            ComponentStorage::SparseSet(_) => StorageType::SparseSet,
+            ComponentStorage::Table(_) => StorageType::Table,
+            ComponentStorage::Tag(_) => StorageType::Tag,
+        }
+    }
+
+    fn get_tracked(&self, index: usize) -> Option<&Tracked<T>> {
+        match self {
+            ComponentStorage::Dense(components) => components.get(index)?.as_ref(),
+            ComponentStorage::SparseSet(components) => components.get(index),
+            ComponentStorage::Table(components) => components.get(index),
+            ComponentStorage::Tag(_) => None,
+        }
+    }
+
+    fn get_tracked_mut(&mut self, index: usize) -> Option<&mut Tracked<T>> {
+        match self {
+            ComponentStorage::Dense(components) => components.get_mut(index)?.as_mut(),
+            ComponentStorage::SparseSet(components) => components.get_mut(index),
+            ComponentStorage::Table(components) => components.get_mut(index),
+            ComponentStorage::Tag(_) => None,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        match self {
+            ComponentStorage::Tag(tags) => tags.contains(index).then(tag),
+            _ => Some(&self.get_tracked(index)?.component),
+        }
+    }
+
+    /// Mutable access that does not flag the component as changed.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match self {
+            ComponentStorage::Tag(tags) => tags.contains(index).then(tag_mut),
+            _ => Some(&mut self.get_tracked_mut(index)?.component),
+        }
+    }
+
+    /// Mutable access to a component and its change ticks, so the caller can decide when it
+    /// counts as changed.
+    pub fn get_with_ticks_mut(&mut self, index: usize) -> Option<(&mut T, &mut ComponentTicks)> {
+        let tracked = self.get_tracked_mut(index)?;
+
+        Some((&mut tracked.component, &mut tracked.ticks))
+    }
+
+    pub fn ticks(&self, index: usize) -> Option<ComponentTicks> {
+        Some(self.get_tracked(index)?.ticks)
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        match self {
+            ComponentStorage::Tag(tags) => tags.contains(index),
+            _ => self.get_tracked(index).is_some(),
+        }
+    }
+
+    /// Stores `component` for the entity. A new component counts as added and changed at
+    /// `change_tick`, a replaced one only as changed. `archetype` is the entity's archetype
+    /// after the insertion and only matters to table storage.
+    pub(super) fn insert(
+        &mut self,
+        index: usize,
+        component: T,
+        change_tick: u32,
+        archetype: ArchetypeId,
+    ) {
+        if let ComponentStorage::Tag(tags) = self {
+            // Nothing is kept of the tag itself, `remove` makes it up again.
+            if tags.insert(index) {
+                std::mem::forget(component);
+            }
+            return;
+        }
+
+        if let Some(tracked) = self.get_tracked_mut(index) {
+            tracked.component = component;
+            tracked.ticks.set_changed(change_tick);
+            return;
+        }
+
+        let tracked = Tracked {
+            component,
+            ticks: ComponentTicks::new(change_tick),
+        };
+        match self {
+            ComponentStorage::Dense(components) => components[index] = Some(tracked),
+            ComponentStorage::SparseSet(components) => components.insert(index, tracked),
+            ComponentStorage::Table(components) => components.insert(index, archetype, tracked),
+            ComponentStorage::Tag(_) => unreachable!("Tags are inserted above"),
+        }
+    }
+
+    pub(super) fn remove(&mut self, index: usize) -> Option<T> {
+        let tracked = match self {
+            // SAFETY: Reading a zero-sized value reads no memory.
+            ComponentStorage::Tag(tags) => {
+                return tags
+                    .remove(index)
+                    .then(|| unsafe { NonNull::<T>::dangling().as_ptr().read() })
+            }
+            ComponentStorage::Dense(components) => components.get_mut(index)?.take(),
+            ComponentStorage::SparseSet(components) => components.remove(index),
+            ComponentStorage::Table(components) => components.remove(index),
+        };
+
+        tracked.map(|tracked| tracked.component)
+    }
+
+    /// Moves the entity's component into another archetype's column. Only table storage keeps
+    /// components per archetype, the other layouts ignore this.
+    pub(super) fn move_to(&mut self, index: usize, archetype: ArchetypeId) {
+        if let ComponentStorage::Table(components) = self {
+            components.move_to(index, archetype);
+        }
+    }
+
+    /// Clamps change ticks older than `MAX_CHANGE_AGE` before `change_tick`.
+    pub(super) fn clamp_ticks(&mut self, change_tick: u32) {
+        let tracked: Box<dyn Iterator<Item = &mut Tracked<T>>> = match self {
+            ComponentStorage::Dense(components) => Box::new(components.iter_mut().flatten()),
+            ComponentStorage::SparseSet(components) => {
+                Box::new(components.iter_mut().map(|(_, tracked)| tracked))
+            }
+            ComponentStorage::Table(components) => {
+                Box::new(components.iter_mut().map(|(_, tracked)| tracked))
+            }
+            ComponentStorage::Tag(_) => return,
+        };
+
+        tracked.for_each(|tracked| tracked.ticks.clamp(change_tick));
+    }
+
+    /// Archetypes holding any components, if this is table storage.
+    pub fn archetypes(&self) -> Option<Vec<ArchetypeId>> {
+        match self {
+            ComponentStorage::Table(components) => Some(components.archetypes().collect()),
+            _ => None,
+        }
+    }
+
+    /// The archetype's components by row, if this is table storage.
+    pub(super) fn column(&self, archetype: ArchetypeId) -> Option<&[Tracked<T>]> {
+        match self {
+            ComponentStorage::Table(components) => Some(components.column(archetype)),
+            _ => None,
+        }
+    }
+
+    pub(super) fn column_mut(&mut self, archetype: ArchetypeId) -> Option<&mut [Tracked<T>]> {
+        match self {
+            ComponentStorage::Table(components) => Some(components.column_mut(archetype)),
+            _ => None,
+        }
+    }
+
+    /// Entity indices of the archetype's rows, if this is table storage.
+    pub fn column_entities(&self, archetype: ArchetypeId) -> Option<&[usize]> {
+        match self {
+            ComponentStorage::Table(components) => Some(components.column_entities(archetype)),
+            _ => None,
+        }
+    }
+
+    /// Entity indices that may hold a component, if the storage can list them without scanning
+    /// every entity. Table storage lists one slice per archetype.
+    pub fn packed_entities(&self) -> Option<Vec<&[usize]>> {
+        match self {
+            ComponentStorage::Dense(_) | ComponentStorage::Tag(_) => None,
+            ComponentStorage::SparseSet(components) => Some(vec![components.entities()]),
+            ComponentStorage::Table(components) => Some(components.entities().collect()),
+        }
+    }
+
+    /// Bytes allocated for the stored components and their lookup tables. Memory owned by the
+    /// components themselves, such as the contents of a `Vec`, is not included.
+    pub fn memory_usage(&self) -> usize {
+        match self {
+            ComponentStorage::Dense(components) => {
+                components.capacity() * size_of::<Option<Tracked<T>>>()
+            }
+            ComponentStorage::SparseSet(components) => components.memory_usage(),
+            ComponentStorage::Table(components) => components.memory_usage(),
+            ComponentStorage::Tag(tags) => tags.memory_usage(),
+        }
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (usize, &T)> + '_> {
+        match self {
+            ComponentStorage::Dense(components) => Box::new(
+                components
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, tracked)| Some((index, &tracked.as_ref()?.component))),
+            ),
+            ComponentStorage::SparseSet(components) => Box::new(
+                components
+                    .iter()
+                    .map(|(index, tracked)| (index, &tracked.component)),
+            ),
+            ComponentStorage::Table(components) => Box::new(
+                components
+                    .iter()
+                    .map(|(index, tracked)| (index, &tracked.component)),
+            ),
+            ComponentStorage::Tag(tags) => Box::new(tags.iter().map(|index| (index, tag()))),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_> {
+        match self {
+            ComponentStorage::Dense(components) => Box::new(
+                components
+                    .iter_mut()
+                    .enumerate()
+                    .filter_map(|(index, tracked)| Some((index, &mut tracked.as_mut()?.component))),
+            ),
+            ComponentStorage::SparseSet(components) => Box::new(
+                components
+                    .iter_mut()
+                    .map(|(index, tracked)| (index, &mut tracked.component)),
+            ),
+            ComponentStorage::Table(components) => Box::new(
+                components
+                    .iter_mut()
+                    .map(|(index, tracked)| (index, &mut tracked.component)),
+            ),
+            ComponentStorage::Tag(tags) => Box::new(tags.iter().map(|index| (index, tag_mut()))),
+        }
+    }
+}
+
+/// A zero-sized tag, which takes no memory to point at.
+fn tag<'a, T>() -> &'a T {
+    // SAFETY: Tag storage only holds zero-sized types, any aligned pointer is valid for them.
+    unsafe { NonNull::dangling().as_ref() }
+}
+
+fn tag_mut<'a, T>() -> &'a mut T {
+    // SAFETY: See `tag`, zero-sized references never overlap.
+    unsafe { NonNull::dangling().as_mut() }
+}
@@ -0,0 +1,205 @@
+use super::archetype::ArchetypeId;
+use std::mem::size_of;
+
+const EMPTY: u32 = u32::MAX;
+
+/// Column and row of an entity's value, packed small since there is one per entity index.
+#[derive(Clone, Copy)]
+struct Location {
+    archetype: u32,
+    row: u32,
+}
+
+struct Column<T> {
+    values: Vec<T>,
+    entities: Vec<usize>,
+}
+
+impl<T> Column<T> {
+    fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            entities: Vec::new(),
+        }
+    }
+}
+
+/// Stores components in one packed column per archetype. Every table stored component of an
+/// entity sits at the same row of its archetype's column, so queries walk the columns of all
+/// their components side by side instead of looking every entity up.
+pub struct Table<T> {
+    locations: Vec<Location>,
+    columns: Vec<Column<T>>,
+    len: usize,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Table<T> {
+    pub fn new() -> Self {
+        Self {
+            locations: Vec::new(),
+            columns: Vec::new(),
+            len: 0,
+        }
+    }
+
+    fn location(&self, index: usize) -> Option<(ArchetypeId, usize)> {
+        match self.locations.get(index) {
+            Some(location) if location.archetype != EMPTY => {
+                Some((location.archetype as usize, location.row as usize))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let (archetype, row) = self.location(index)?;
+
+        self.columns[archetype].values.get(row)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let (archetype, row) = self.location(index)?;
+
+        self.columns[archetype].values.get_mut(row)
+    }
+
+    /// Stores the value at the end of the archetype's column, or in place if the entity already
+    /// has one.
+    pub fn insert(&mut self, index: usize, archetype: ArchetypeId, value: T) {
+        if let Some(current) = self.get_mut(index) {
+            *current = value;
+            return;
+        }
+
+        if archetype >= self.columns.len() {
+            self.columns.resize_with(archetype + 1, Column::new);
+        }
+        if index >= self.locations.len() {
+            self.locations.resize(
+                index + 1,
+                Location {
+                    archetype: EMPTY,
+                    row: 0,
+                },
+            );
+        }
+
+        let column = &mut self.columns[archetype];
+        self.locations[index] = Location {
+            archetype: archetype as u32,
+            row: column.values.len() as u32,
+        };
+        column.values.push(value);
+        column.entities.push(index);
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let (archetype, row) = self.location(index)?;
+        let column = &mut self.columns[archetype];
+
+        // Move the last row into the hole and repoint its entity at the new location.
+        let value = column.values.swap_remove(row);
+        column.entities.swap_remove(row);
+        if let Some(&moved_index) = column.entities.get(row) {
+            self.locations[moved_index].row = row as u32;
+        }
+        self.locations[index].archetype = EMPTY;
+        self.len -= 1;
+
+        Some(value)
+    }
+
+    /// Moves the entity's value to the end of another archetype's column. Does nothing if the
+    /// entity has no value here.
+    pub fn move_to(&mut self, index: usize, archetype: ArchetypeId) {
+        match self.location(index) {
+            Some((current, _)) if current != archetype => {
+                if let Some(value) = self.remove(index) {
+                    self.insert(index, archetype, value);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes allocated for every column and the location table.
+    pub fn memory_usage(&self) -> usize {
+        let columns: usize = self
+            .columns
+            .iter()
+            .map(|column| {
+                column.values.capacity() * size_of::<T>()
+                    + column.entities.capacity() * size_of::<usize>()
+            })
+            .sum();
+
+        columns
+            + self.columns.capacity() * size_of::<Column<T>>()
+            + self.locations.capacity() * size_of::<Location>()
+    }
+
+    /// Archetypes whose column holds any values.
+    pub fn archetypes(&self) -> impl Iterator<Item = ArchetypeId> + '_ {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| !column.values.is_empty())
+            .map(|(archetype, _)| archetype)
+    }
+
+    /// The values stored for the archetype, by row.
+    pub fn column(&self, archetype: ArchetypeId) -> &[T] {
+        self.columns
+            .get(archetype)
+            .map_or(&[], |column| column.values.as_slice())
+    }
+
+    pub fn column_mut(&mut self, archetype: ArchetypeId) -> &mut [T] {
+        self.columns
+            .get_mut(archetype)
+            .map_or(&mut [], |column| column.values.as_mut_slice())
+    }
+
+    /// Entity indices of the archetype's rows.
+    pub fn column_entities(&self, archetype: ArchetypeId) -> &[usize] {
+        self.columns
+            .get(archetype)
+            .map_or(&[], |column| column.entities.as_slice())
+    }
+
+    /// Entity indices of every column, in the same order as the stored values.
+    pub fn entities(&self) -> impl Iterator<Item = &[usize]> {
+        self.columns.iter().map(|column| column.entities.as_slice())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.columns
+            .iter()
+            .flat_map(|column| column.entities.iter().copied().zip(column.values.iter()))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.columns.iter_mut().flat_map(|column| {
+            column
+                .entities
+                .iter()
+                .copied()
+                .zip(column.values.iter_mut())
+        })
+    }
+}
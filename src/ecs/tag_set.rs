@@ -0,0 +1,153 @@
+use std::mem::size_of;
+
+const BITS: usize = u64::BITS as usize;
+
+/// Which entities have a zero-sized tag component, one bit per entity index up to the highest
+/// tagged one. A tag has no value to store, so membership is all there is.
+pub struct TagSet {
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl Default for TagSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TagSet {
+    pub fn new() -> Self {
+        Self {
+            bits: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.bits
+            .get(index / BITS)
+            .is_some_and(|word| word & (1 << (index % BITS)) != 0)
+    }
+
+    /// Tags the entity, returning whether it wasn't tagged yet.
+    pub fn insert(&mut self, index: usize) -> bool {
+        if index / BITS >= self.bits.len() {
+            self.bits.resize(index / BITS + 1, 0);
+        }
+
+        let word = &mut self.bits[index / BITS];
+        let bit = 1 << (index % BITS);
+        let is_new = *word & bit == 0;
+        *word |= bit;
+        if is_new {
+            self.len += 1;
+        }
+
+        is_new
+    }
+
+    /// Untags the entity, returning whether it was tagged.
+    pub fn remove(&mut self, index: usize) -> bool {
+        let Some(word) = self.bits.get_mut(index / BITS) else {
+            return false;
+        };
+
+        let bit = 1 << (index % BITS);
+        let was_tagged = *word & bit != 0;
+        *word &= !bit;
+        if was_tagged {
+            self.len -= 1;
+        }
+
+        was_tagged
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes allocated for the bits.
+    pub fn memory_usage(&self) -> usize {
+        self.bits.capacity() * size_of::<u64>()
+    }
+
+    /// Tagged entity indices in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, &word)| {
+                (0..BITS)
+                    .filter(move |bit| word & (1 << bit) != 0)
+                    .map(move |bit| word_index * BITS + bit)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::{Ecs, StorageType, With};
+
+    #[test]
+    fn tags_are_one_bit_per_entity() {
+        let mut tags = TagSet::new();
+        assert!(tags.insert(3));
+        assert!(tags.insert(130));
+        assert!(!tags.insert(3));
+        assert!(tags.remove(130));
+        assert!(!tags.remove(130));
+        assert!(!tags.remove(1000));
+        assert!(tags.insert(64));
+
+        assert_eq!(tags.iter().collect::<Vec<_>>(), vec![3, 64]);
+        assert_eq!(tags.len(), 2);
+        assert!(tags.contains(64) && !tags.contains(65));
+    }
+
+    struct Player;
+
+    struct Position(u32);
+
+    #[test]
+    fn zero_sized_components_are_stored_as_tags() {
+        let mut ecs = Ecs::new();
+        ecs.set_default_storage(StorageType::Table);
+        ecs.register_component::<Player>()
+            .expect("Could not register component");
+        ecs.register_component::<Position>()
+            .expect("Could not register component");
+        for index in 0..100 {
+            let entity = ecs
+                .spawn((Position(index),))
+                .expect("Could not spawn entity");
+            if index % 10 == 0 {
+                ecs.add_component(entity, Player)
+                    .expect("Could not add component");
+            }
+        }
+
+        let memory = ecs.memory_usage().expect("Could not get memory usage");
+        let players = memory
+            .components
+            .iter()
+            .find(|component| component.name.ends_with("Player"))
+            .expect("Missing player memory");
+        assert_eq!(players.storage_type, StorageType::Tag);
+        // 100 bits fit in two words, the rest is spare capacity.
+        assert!(players.bytes <= 4 * size_of::<u64>());
+
+        let mut tagged: Vec<u32> = ecs
+            .query_filtered::<&Position, With<Player>>()
+            .expect("Could not query players")
+            .iter()
+            .map(|(_, position)| position.0)
+            .collect();
+        tagged.sort();
+        assert_eq!(tagged, (0..100).step_by(10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,11 @@
+use crate::ecs::Entity;
+
+/// Sent by the physics system when a falling rigid body hits the ground.
+pub struct LandedEvent {
+    pub entity: Entity,
+}
+
+/// Sent by the controller system for every controlled entity when jump is pressed.
+pub struct JumpPressedEvent {
+    pub entity: Entity,
+}
@@ -0,0 +1,169 @@
+use crate::{
+    ecs::{Ecs, EcsError},
+    time::Time,
+};
+use std::time::Instant;
+
+/// Source of the current time in milliseconds, counted from any fixed point.
+pub trait Clock {
+    fn now(&mut self) -> f32;
+}
+
+/// Wall clock time since the clock was created.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&mut self) -> f32 {
+        self.start.elapsed().as_secs_f32() * 1000.0
+    }
+}
+
+/// Drives the world's [`Time`] resource from a clock. Each frame, [`GameLoop::advance`] says how
+/// many fixed ticks to run before rendering, e.g.
+///
+/// ```ignore
+/// let ticks = game_loop.advance(&ecs)?;
+/// for _ in 0..ticks {
+///     schedule.run_stage(Stage::FixedUpdate, &mut ecs)?;
+/// }
+/// schedule.run_stage(Stage::Render, &mut ecs)?;
+/// ```
+pub struct GameLoop<C: Clock = SystemClock> {
+    clock: C,
+}
+
+impl GameLoop<SystemClock> {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock::new())
+    }
+}
+
+impl Default for GameLoop<SystemClock> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> GameLoop<C> {
+    /// Uses `clock` instead of wall clock time, e.g. a manually advanced clock in tests.
+    pub fn with_clock(clock: C) -> Self {
+        Self { clock }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut C {
+        &mut self.clock
+    }
+
+    /// Starts a frame: reads the clock into the `Time` resource and returns the number of fixed
+    /// ticks due.
+    pub fn advance(&mut self, ecs: &Ecs) -> Result<u32, EcsError> {
+        let now = self.clock.now();
+
+        Ok(ecs.resource_mut::<Time>()?.advance_to(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{MAX_TICKS_PER_FRAME, TICK_RATE};
+
+    /// Only moves when told to.
+    struct ManualClock(f32);
+
+    impl Clock for ManualClock {
+        fn now(&mut self) -> f32 {
+            self.0
+        }
+    }
+
+    fn game_loop() -> (GameLoop<ManualClock>, Ecs) {
+        let mut ecs = Ecs::new();
+        ecs.insert_resource(Time::new());
+
+        (GameLoop::with_clock(ManualClock(0.0)), ecs)
+    }
+
+    /// Moves the clock on by `delta` and starts a frame.
+    fn frame(game_loop: &mut GameLoop<ManualClock>, ecs: &Ecs, delta: f32) -> u32 {
+        game_loop.clock_mut().0 += delta;
+        game_loop.advance(ecs).expect("Could not advance")
+    }
+
+    fn alpha(ecs: &Ecs) -> f32 {
+        ecs.resource::<Time>().expect("Could not get time").alpha()
+    }
+
+    #[test]
+    fn long_frames_run_several_ticks() {
+        let (mut game_loop, ecs) = game_loop();
+
+        assert_eq!(frame(&mut game_loop, &ecs, TICK_RATE * 3.5), 3);
+        assert_eq!(frame(&mut game_loop, &ecs, TICK_RATE * 1.75), 2);
+
+        let time = ecs.resource::<Time>().expect("Could not get time");
+        assert_eq!(time.tick_count(), 5);
+        assert!((time.elapsed() - TICK_RATE * 5.25).abs() < 1e-3);
+        assert!((time.delta() - TICK_RATE * 1.75).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ticks_are_clamped_and_the_excess_dropped() {
+        let (mut game_loop, ecs) = game_loop();
+
+        let behind = MAX_TICKS_PER_FRAME as f32 + 10.25;
+        assert_eq!(
+            frame(&mut game_loop, &ecs, TICK_RATE * behind),
+            MAX_TICKS_PER_FRAME
+        );
+        assert!((alpha(&ecs) - 0.25).abs() < 1e-3);
+
+        // Only the part of a tick left over carries on.
+        assert_eq!(frame(&mut game_loop, &ecs, TICK_RATE * 0.5), 0);
+        assert_eq!(frame(&mut game_loop, &ecs, TICK_RATE * 0.5), 1);
+        assert_eq!(
+            ecs.resource::<Time>()
+                .expect("Could not get time")
+                .tick_count(),
+            MAX_TICKS_PER_FRAME + 1
+        );
+    }
+
+    #[test]
+    fn alpha_is_how_far_the_frame_is_into_the_next_tick() {
+        let (mut game_loop, ecs) = game_loop();
+
+        assert_eq!(frame(&mut game_loop, &ecs, TICK_RATE * 0.25), 0);
+        assert!((alpha(&ecs) - 0.25).abs() < 1e-3);
+
+        assert_eq!(frame(&mut game_loop, &ecs, TICK_RATE * 0.5), 0);
+        assert!((alpha(&ecs) - 0.75).abs() < 1e-3);
+
+        assert_eq!(frame(&mut game_loop, &ecs, TICK_RATE * 0.5), 1);
+        assert!((alpha(&ecs) - 0.25).abs() < 1e-3);
+
+        // A clock going backwards neither runs ticks nor moves the frame back.
+        assert_eq!(frame(&mut game_loop, &ecs, -TICK_RATE), 0);
+        assert!((alpha(&ecs) - 0.25).abs() < 1e-3);
+    }
+}
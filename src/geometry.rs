@@ -0,0 +1,440 @@
+use crate::ray::Ray;
+use nalgebra_glm::Vec3;
+
+/// Lengths and determinants below this are treated as zero.
+const EPSILON: f32 = 1e-6;
+
+/// Where a ray first hits a shape. `distance` is measured in lengths of the ray's direction,
+/// and `normal` is the unit surface normal on the side the ray came from. Rays starting inside
+/// a solid shape hit it right away, with a normal against the ray's direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// Weights of the triangle's three vertices at `point`, only set for triangle hits.
+    pub barycentric: Option<Vec3>,
+}
+
+impl RayHit {
+    fn new(ray: &Ray, distance: f32, normal: Vec3) -> Self {
+        Self {
+            distance,
+            point: ray.at(distance),
+            normal,
+            barycentric: None,
+        }
+    }
+
+    fn inside(ray: &Ray) -> Self {
+        Self::new(ray, 0.0, -ray.direction().normalize())
+    }
+}
+
+/// The side of `normal` facing the ray's origin.
+fn facing(ray: &Ray, normal: Vec3) -> Vec3 {
+    if normal.dot(&ray.direction()) > 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+/// Hits the plane of points `p` with `p · normal == offset`, from either side.
+pub fn ray_plane(ray: &Ray, normal: Vec3, offset: f32) -> Option<RayHit> {
+    let denominator = ray.direction().dot(&normal);
+    if denominator.abs() < EPSILON {
+        return None;
+    }
+
+    let distance = (offset - ray.origin().dot(&normal)) / denominator;
+    if distance < 0.0 {
+        return None;
+    }
+
+    Some(RayHit::new(ray, distance, facing(ray, normal.normalize())))
+}
+
+/// Möller–Trumbore intersection, hitting the triangle from either side.
+pub fn ray_triangle(ray: &Ray, triangle: &[Vec3; 3]) -> Option<RayHit> {
+    let [a, b, c] = *triangle;
+    let edge_ab = b - a;
+    let edge_ac = c - a;
+
+    let p = ray.direction().cross(&edge_ac);
+    let determinant = edge_ab.dot(&p);
+    if determinant.abs() < EPSILON {
+        return None;
+    }
+    let inverse = 1.0 / determinant;
+
+    let from_a = ray.origin() - a;
+    let u = from_a.dot(&p) * inverse;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = from_a.cross(&edge_ab);
+    let v = ray.direction().dot(&q) * inverse;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let distance = edge_ac.dot(&q) * inverse;
+    if distance < 0.0 {
+        return None;
+    }
+
+    Some(RayHit {
+        barycentric: Some(Vec3::new(1.0 - u - v, u, v)),
+        ..RayHit::new(
+            ray,
+            distance,
+            facing(ray, edge_ab.cross(&edge_ac).normalize()),
+        )
+    })
+}
+
+/// Slab test against the box between the corners `min` and `max`.
+pub fn ray_aabb(ray: &Ray, min: Vec3, max: Vec3) -> Option<RayHit> {
+    let origin = ray.origin();
+    let direction = ray.direction();
+    let mut enter = f32::MIN;
+    let mut exit = f32::MAX;
+    let mut normal = Vec3::zeros();
+
+    for axis in 0..3 {
+        if direction[axis].abs() < EPSILON {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let to_min = (min[axis] - origin[axis]) / direction[axis];
+        let to_max = (max[axis] - origin[axis]) / direction[axis];
+        let (near, far) = if to_min < to_max {
+            (to_min, to_max)
+        } else {
+            (to_max, to_min)
+        };
+
+        if near > enter {
+            enter = near;
+            normal = Vec3::zeros();
+            normal[axis] = -direction[axis].signum();
+        }
+        exit = exit.min(far);
+    }
+
+    if exit < enter.max(0.0) {
+        return None;
+    }
+    if enter < 0.0 {
+        return Some(RayHit::inside(ray));
+    }
+
+    Some(RayHit::new(ray, enter, normal))
+}
+
+pub fn ray_sphere(ray: &Ray, center: Vec3, radius: f32) -> Option<RayHit> {
+    let from_center = ray.origin() - center;
+    let outside = from_center.norm_squared() - radius * radius;
+    if outside <= 0.0 {
+        return Some(RayHit::inside(ray));
+    }
+
+    let a = ray.direction().norm_squared();
+    let b = from_center.dot(&ray.direction());
+    let discriminant = b * b - a * outside;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    // The origin is outside, so a hit behind it means the whole sphere is behind it.
+    let distance = (-b - discriminant.sqrt()) / a;
+    if distance < 0.0 {
+        return None;
+    }
+
+    let point = ray.at(distance);
+    Some(RayHit::new(ray, distance, (point - center) / radius))
+}
+
+/// Hits the capsule of points within `radius` of the segment from `start` to `end`.
+pub fn ray_capsule(ray: &Ray, start: Vec3, end: Vec3, radius: f32) -> Option<RayHit> {
+    let axis = end - start;
+    let length_squared = axis.norm_squared();
+    if length_squared < EPSILON {
+        return ray_sphere(ray, start, radius);
+    }
+
+    let from_start = ray.origin() - start;
+    let along_origin = axis.dot(&from_start) / length_squared;
+    let closest = start + axis * along_origin.clamp(0.0, 1.0);
+    if (ray.origin() - closest).norm_squared() <= radius * radius {
+        return Some(RayHit::inside(ray));
+    }
+
+    // The side of the infinite cylinder around the axis, kept if it is hit between the caps.
+    // Any hit on the end spheres would lie behind it.
+    let along_direction = axis.dot(&ray.direction()) / length_squared;
+    let direction_across = ray.direction() - axis * along_direction;
+    let origin_across = from_start - axis * along_origin;
+    let a = direction_across.norm_squared();
+    let b = origin_across.dot(&direction_across);
+    let c = origin_across.norm_squared() - radius * radius;
+    let discriminant = b * b - a * c;
+    if a > EPSILON && discriminant >= 0.0 {
+        let distance = (-b - discriminant.sqrt()) / a;
+        let along = along_origin + distance * along_direction;
+        if distance >= 0.0 && (0.0..=1.0).contains(&along) {
+            let point = ray.at(distance);
+            let normal = (point - (start + axis * along)) / radius;
+            return Some(RayHit::new(ray, distance, normal));
+        }
+    }
+
+    [ray_sphere(ray, start, radius), ray_sphere(ray, end, radius)]
+        .into_iter()
+        .flatten()
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f32 = 1e-5;
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+        Ray::new(Vec3::from(origin), Vec3::from(direction))
+    }
+
+    fn assert_hit(hit: Option<RayHit>, distance: f32, point: [f32; 3], normal: [f32; 3]) {
+        let hit = hit.expect("Missing hit");
+        assert!((hit.distance - distance).abs() < TOLERANCE, "{hit:?}");
+        assert!(
+            (hit.point - Vec3::from(point)).norm() < TOLERANCE,
+            "{hit:?}"
+        );
+        assert!(
+            (hit.normal - Vec3::from(normal)).norm() < TOLERANCE,
+            "{hit:?}"
+        );
+    }
+
+    #[test]
+    fn planes_are_hit_at_their_offset() {
+        let up = Vec3::y();
+        assert_hit(
+            ray_plane(&ray([1.0, 10.0, 0.0], [0.0, -1.0, 0.0]), up, 5.0),
+            5.0,
+            [1.0, 5.0, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+        // Below the plane at y = 5, pointing away from it, where a plane through the origin
+        // would be hit.
+        assert!(ray_plane(&ray([1.0, 3.0, 0.0], [0.0, -1.0, 0.0]), up, 5.0).is_none());
+        assert_hit(
+            ray_plane(&ray([1.0, 3.0, 0.0], [0.0, 1.0, 0.0]), up, 5.0),
+            2.0,
+            [1.0, 5.0, 0.0],
+            [0.0, -1.0, 0.0],
+        );
+
+        // The offset is along the normal as given, the hit normal is made unit length, and the
+        // distance counts lengths of the direction.
+        assert_hit(
+            ray_plane(&ray([0.0, 10.0, 0.0], [0.0, -2.0, 0.0]), up * 2.0, 10.0),
+            2.5,
+            [0.0, 5.0, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+    }
+
+    fn triangle() -> [Vec3; 3] {
+        [
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(2.0, 5.0, 0.0),
+            Vec3::new(0.0, 5.0, 2.0),
+        ]
+    }
+
+    #[test]
+    fn triangles_report_barycentrics() {
+        let hit = ray_triangle(&ray([0.5, 10.0, 0.5], [0.0, -1.0, 0.0]), &triangle());
+        assert_hit(hit, 5.0, [0.5, 5.0, 0.5], [0.0, 1.0, 0.0]);
+        let barycentric = hit
+            .and_then(|hit| hit.barycentric)
+            .expect("Missing barycentric");
+        assert!((barycentric - Vec3::new(0.5, 0.25, 0.25)).norm() < TOLERANCE);
+
+        // From below the normal faces down, and each vertex has all the weight at itself.
+        for (index, vertex) in triangle().into_iter().enumerate() {
+            let hit = ray_triangle(
+                &ray([vertex.x, 0.0, vertex.z], [0.0, 1.0, 0.0]),
+                &triangle(),
+            );
+            assert_hit(hit, 5.0, vertex.into(), [0.0, -1.0, 0.0]);
+            let barycentric = hit
+                .and_then(|hit| hit.barycentric)
+                .expect("Missing barycentric");
+            assert!((barycentric[index] - 1.0).abs() < TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn triangles_are_missed_past_each_edge() {
+        // Just outside the edges opposite c, b and a.
+        for [x, z] in [[0.5, -0.01], [-0.01, 0.5], [1.01, 1.0]] {
+            let hit = ray_triangle(&ray([x, 10.0, z], [0.0, -1.0, 0.0]), &triangle());
+            assert!(hit.is_none(), "{x} {z}");
+        }
+        // Behind the ray.
+        assert!(ray_triangle(&ray([0.5, 4.0, 0.5], [0.0, -1.0, 0.0]), &triangle()).is_none());
+    }
+
+    #[test]
+    fn boxes_are_hit_on_the_entered_face() {
+        let (min, max) = (Vec3::repeat(-1.0), Vec3::repeat(1.0));
+        assert_hit(
+            ray_aabb(&ray([-5.0, 0.2, 0.0], [1.0, 0.0, 0.0]), min, max),
+            4.0,
+            [-1.0, 0.2, 0.0],
+            [-1.0, 0.0, 0.0],
+        );
+        assert_hit(
+            ray_aabb(&ray([0.5, 5.0, 0.0], [0.0, -1.0, 0.0]), min, max),
+            4.0,
+            [0.5, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+        assert!(ray_aabb(&ray([5.0, 0.0, 0.0], [1.0, 0.0, 0.0]), min, max).is_none());
+        assert!(ray_aabb(&ray([-5.0, 0.0, 0.0], [1.0, 1.0, 0.0]), min, max).is_none());
+    }
+
+    #[test]
+    fn boxes_are_hit_right_away_from_inside() {
+        let (min, max) = (Vec3::repeat(-1.0), Vec3::repeat(1.0));
+        assert_hit(
+            ray_aabb(&ray([0.0, 0.5, 0.0], [0.0, -2.0, 0.0]), min, max),
+            0.0,
+            [0.0, 0.5, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+        let diagonal = Vec3::new(1.0, 1.0, 1.0).normalize();
+        assert_hit(
+            ray_aabb(&ray([0.0, 0.0, 0.0], diagonal.into()), min, max),
+            0.0,
+            [0.0, 0.0, 0.0],
+            (-diagonal).into(),
+        );
+    }
+
+    #[test]
+    fn spheres_are_hit_when_grazed_or_started_in() {
+        let center = Vec3::zeros();
+        assert_hit(
+            ray_sphere(&ray([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]), center, 1.0),
+            4.0,
+            [0.0, 0.0, -1.0],
+            [0.0, 0.0, -1.0],
+        );
+        assert_hit(
+            ray_sphere(&ray([0.0, 1.0, -5.0], [0.0, 0.0, 1.0]), center, 1.0),
+            5.0,
+            [0.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+        assert!(ray_sphere(&ray([0.0, 1.01, -5.0], [0.0, 0.0, 1.0]), center, 1.0).is_none());
+        assert!(ray_sphere(&ray([0.0, 0.0, 5.0], [0.0, 0.0, 1.0]), center, 1.0).is_none());
+        assert_hit(
+            ray_sphere(&ray([0.0, 0.0, 0.5], [0.0, 0.0, 3.0]), center, 1.0),
+            0.0,
+            [0.0, 0.0, 0.5],
+            [0.0, 0.0, -1.0],
+        );
+    }
+
+    /// Capsule standing upright from `y = -1` to `y = 1`.
+    fn capsule(ray: &Ray) -> Option<RayHit> {
+        ray_capsule(
+            ray,
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.5,
+        )
+    }
+
+    #[test]
+    fn capsules_are_hit_on_the_side_and_caps() {
+        assert_hit(
+            capsule(&ray([-5.0, 0.5, 0.0], [1.0, 0.0, 0.0])),
+            4.5,
+            [-0.5, 0.5, 0.0],
+            [-1.0, 0.0, 0.0],
+        );
+        assert_hit(
+            capsule(&ray([0.0, 5.0, 0.0], [0.0, -1.0, 0.0])),
+            3.5,
+            [0.0, 1.5, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+        // Over the top end, where only the cap sticks out.
+        let across = 0.21_f32.sqrt();
+        let hit = capsule(&ray([-5.0, 1.2, 0.0], [1.0, 0.0, 0.0]));
+        assert_hit(
+            hit,
+            5.0 - across,
+            [-across, 1.2, 0.0],
+            [-across / 0.5, 0.2 / 0.5, 0.0],
+        );
+    }
+
+    #[test]
+    fn capsules_are_hit_when_grazed_or_started_in() {
+        assert_hit(
+            capsule(&ray([-5.0, 0.0, 0.5], [1.0, 0.0, 0.0])),
+            5.0,
+            [0.0, 0.0, 0.5],
+            [0.0, 0.0, 1.0],
+        );
+        assert_hit(
+            capsule(&ray([-5.0, 1.5, 0.0], [1.0, 0.0, 0.0])),
+            5.0,
+            [0.0, 1.5, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+        assert!(capsule(&ray([-5.0, 1.51, 0.0], [1.0, 0.0, 0.0])).is_none());
+        assert_hit(
+            capsule(&ray([0.0, 1.2, 0.0], [1.0, 0.0, 0.0])),
+            0.0,
+            [0.0, 1.2, 0.0],
+            [-1.0, 0.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn parallel_rays_miss() {
+        let along_x = [1.0, 0.0, 0.0];
+        assert!(ray_plane(&ray([0.0, 6.0, 0.0], along_x), Vec3::y(), 5.0).is_none());
+        // Even when running inside the plane.
+        assert!(ray_plane(&ray([0.0, 5.0, 0.0], along_x), Vec3::y(), 5.0).is_none());
+        assert!(ray_triangle(&ray([-1.0, 5.0, 0.5], along_x), &triangle()).is_none());
+        assert!(ray_triangle(&ray([-1.0, 6.0, 0.5], along_x), &triangle()).is_none());
+        // Outside the box's slab on an axis the ray doesn't move along.
+        let (min, max) = (Vec3::repeat(-1.0), Vec3::repeat(1.0));
+        assert!(ray_aabb(&ray([-5.0, 1.5, 0.0], along_x), min, max).is_none());
+        assert!(ray_aabb(&ray([-5.0, 0.0, -1.5], along_x), min, max).is_none());
+        // Alongside the capsule's axis, past its radius.
+        assert!(capsule(&ray([0.6, -5.0, 0.0], [0.0, 1.0, 0.0])).is_none());
+        // Within it, the side can't be hit but the end facing the ray can.
+        assert_hit(
+            capsule(&ray([0.3, -5.0, 0.0], [0.0, 1.0, 0.0])),
+            5.0 - 1.0 - 0.4,
+            [0.3, -1.4, 0.0],
+            [0.6, -0.8, 0.0],
+        );
+    }
+}
@@ -1,16 +1,23 @@
+pub mod broadphase;
+pub mod bundles;
 pub mod camera;
 pub mod collider;
 pub mod components;
 pub mod constants;
 pub mod ecs;
+pub mod events;
+pub mod game_loop;
+pub mod geometry;
 pub mod level;
 pub mod mesh;
 pub mod mesh_manager;
 pub mod models;
+pub mod narrow_phase;
 pub mod ray;
 pub mod resources;
 pub mod shader;
 pub mod systems;
 pub mod textures;
+pub mod time;
 pub mod utils;
 pub mod vertex;
@@ -1,23 +1,41 @@
 use goblin_game::{
+    bundles::{PlayerBundle, StaticPropBundle},
+    camera::Camera,
     collider::Collider,
     components::{
-        camera_followable::CameraFollowable, controllable::Controllable, gravity::GravityComponent,
-        mesh::MeshComponent, rigid_body::RigidBody, transform::Transform,
+        self,
+        camera_followable::CameraFollowable,
+        collider_shape::ColliderShape,
+        collision_layers::CollisionLayers,
+        controllable::Controllable,
+        global_transform::GlobalTransform,
+        gravity::GravityComponent,
+        mesh::MeshComponent,
+        rigid_body::RigidBody,
+        static_collider::StaticCollider,
+        tags::{Enemy, Player, Static},
+        transform::Transform,
     },
-    constants::{SCREEN_HEIGHT, SCREEN_WIDTH, TICK_RATE},
-    ecs::Ecs,
+    constants::{SCREEN_HEIGHT, SCREEN_WIDTH},
+    ecs::{Ecs, Name, StorageType},
+    events::{JumpPressedEvent, LandedEvent},
+    game_loop::GameLoop,
     mesh_manager::MeshManager,
     models::{cube::Cube, plane::Plane},
     resources::Resources,
     shader::Shader,
     systems::{
-        controller_system::ControllerSystem, physics_system::PhysicsSystem,
-        render_system::RenderSystem, System, SystemError,
+        controller_system::ControllerSystem,
+        physics_system::PhysicsSystem,
+        render_system::RenderSystem,
+        schedule::{Schedule, Stage},
+        transform_system::TransformSystem,
     },
     textures::texture_manager::{TextureId, TextureManager},
+    time::Time,
 };
 use nalgebra_glm as glm;
-use std::{path::Path, sync::Mutex};
+use std::path::Path;
 
 fn main() {
     let res = Resources::from_relative_exe_path(Path::new("assets")).unwrap();
@@ -52,158 +70,172 @@ fn main() {
     }
 
     let shader = Shader::from_resource(&res, "shaders/triangle").unwrap();
-    let mesh_manager = Mutex::new(MeshManager::new());
-    let mut other_tmp = mesh_manager.lock().expect("Could not lock mesh manager.");
+    let mut mesh_manager = MeshManager::new();
     let texture_manager = TextureManager::new(&res);
 
-    let ecs = Mutex::new(Ecs::new());
-    let mut tmp = ecs.lock().expect("Could not lock ECS.");
-    tmp.register_component::<Transform>();
-    tmp.register_component::<MeshComponent>();
-    tmp.register_component::<RigidBody>();
-    tmp.register_component::<GravityComponent>();
-    tmp.register_component::<Controllable>();
-    tmp.register_component::<CameraFollowable>();
+    let mut ecs = Ecs::new();
+    ecs.set_default_storage(StorageType::Table);
+    ecs.register_component::<Transform>()
+        .expect("Could not register component");
+    ecs.register_component::<GlobalTransform>()
+        .expect("Could not register component");
+    ecs.register_component::<MeshComponent>()
+        .expect("Could not register component");
+    ecs.register_component::<RigidBody>()
+        .expect("Could not register component");
+    ecs.register_component::<GravityComponent>()
+        .expect("Could not register component");
+    ecs.register_component_with_storage::<Controllable>(StorageType::SparseSet)
+        .expect("Could not register component");
+    ecs.register_component_with_storage::<CameraFollowable>(StorageType::SparseSet)
+        .expect("Could not register component");
+    ecs.register_component::<Player>()
+        .expect("Could not register component");
+    ecs.register_component::<Enemy>()
+        .expect("Could not register component");
+    ecs.register_component::<Static>()
+        .expect("Could not register component");
+    ecs.register_component::<StaticCollider>()
+        .expect("Could not register component");
+    ecs.register_component::<ColliderShape>()
+        .expect("Could not register component");
+    ecs.register_component::<CollisionLayers>()
+        .expect("Could not register component");
+    StaticCollider::register_hooks(&mut ecs);
+    components::register_serializable(&mut ecs);
+    components::register_snapshots(&mut ecs);
 
     let grass_texture = texture_manager.get_texture(TextureId::Grass);
     let stone_brick_texture = texture_manager.get_texture(TextureId::StoneBricks);
 
-    let plane_id = other_tmp.add_mesh(Plane::get_mesh(vec![stone_brick_texture]));
-    let cube_id = other_tmp.add_mesh(Cube::get_mesh(vec![grass_texture]));
+    let plane_id = mesh_manager.add_mesh("plane", Plane::get_mesh(vec![stone_brick_texture]));
+    let cube_id = mesh_manager.add_mesh("cube", Cube::get_mesh(vec![grass_texture]));
+
+    ecs.spawn(StaticPropBundle::new(
+        "Floor",
+        plane_id,
+        Transform::new(
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            None,
+            Some(glm::Vec3::new(101.0, 1.0, 101.0)),
+        ),
+    ))
+    .expect("Could not spawn floor");
+
+    ecs.spawn(
+        StaticPropBundle::new(
+            "Wall 1",
+            plane_id,
+            Transform::new(
+                glm::Vec3::new(50.0, 5.0, 0.0),
+                Some(glm::Vec4::new(90.0, 0.0, 0.0, 1.0)),
+                Some(glm::Vec3::new(10.0, 1.0, 101.0)),
+            ),
+        )
+        .with_collider(ColliderShape::plane()),
+    )
+    .expect("Could not spawn wall");
+
+    ecs.spawn(
+        StaticPropBundle::new(
+            "Block 1",
+            cube_id,
+            Transform::new(glm::Vec3::new(0.0, 1.0, 0.0), None, None),
+        )
+        .with_collider(ColliderShape::cube()),
+    )
+    .expect("Could not spawn block");
+
+    ecs.spawn(
+        StaticPropBundle::new(
+            "Block 2",
+            cube_id,
+            Transform::new(glm::Vec3::new(1.0, 2.0, 1.0), None, None),
+        )
+        .with_collider(ColliderShape::cube()),
+    )
+    .expect("Could not spawn block");
+
+    ecs.spawn((
+        Name::new("Falling Block"),
+        MeshComponent { id: cube_id },
+        Transform::new(
+            glm::Vec3::new(0.0, 5.0, 0.0),
+            Some(glm::Vec4::new(45.0, 0.0, 1.0, 0.0)),
+            None,
+        ),
+        GlobalTransform::default(),
+        RigidBody::default(),
+        GravityComponent::default(),
+        ColliderShape::cube(),
+    ))
+    .expect("Could not spawn falling block");
+
+    ecs.spawn(PlayerBundle::new(glm::Vec3::new(-1.0, 4.0, 0.0)))
+        .expect("Could not spawn player");
+
+    ecs.spawn((
+        Name::new("Floor Collider"),
+        StaticCollider,
+        Transform::new(
+            glm::Vec3::zeros(),
+            None,
+            Some(glm::Vec3::new(101.0, 0.01, 101.0)),
+        ),
+    ))
+    .expect("Could not spawn floor collider");
+
+    ecs.insert_resource(Collider::new());
+    ecs.insert_resource(mesh_manager);
+    ecs.insert_resource(texture_manager);
+    ecs.insert_resource(Camera::new());
+    ecs.insert_resource(Time::new());
+    ecs.add_event::<LandedEvent>();
+    ecs.add_event::<JumpPressedEvent>();
+    ecs.apply_commands()
+        .expect("Could not apply setup commands");
+
+    let mut schedule = Schedule::new();
 
-    drop(other_tmp);
-
-    // Floor
-    let model = MeshComponent { id: plane_id };
-    let transform = Transform::new(
-        glm::Vec3::new(0.0, 0.0, 0.0),
-        None,
-        Some(glm::Vec3::new(101.0, 1.0, 101.0)),
-    );
-    let floor = tmp.create_entity().expect("Could not create entity");
-    tmp.add_component(floor, model)
-        .expect("Could not add component");
-    tmp.add_component(floor, transform)
-        .expect("Could not add component");
-
-    // Wall 1
-    let model = MeshComponent { id: plane_id };
-    let transform = Transform::new(
-        glm::Vec3::new(50.0, 5.0, 0.0),
-        Some(glm::Vec4::new(90.0, 0.0, 0.0, 1.0)),
-        Some(glm::Vec3::new(10.0, 1.0, 101.0)),
-    );
-    let wall1 = tmp.create_entity().expect("Could not create entity");
-    tmp.add_component(wall1, model)
-        .expect("Could not add component");
-    tmp.add_component(wall1, transform)
-        .expect("Could not add component");
-
-    // Block 1
-    let model = MeshComponent { id: cube_id };
-    let transform = Transform::new(glm::Vec3::new(0.0, 1.0, 0.0), None, None);
-    let block1 = tmp.create_entity().expect("Could not create entity");
-    tmp.add_component(block1, model)
-        .expect("Could not add component");
-    tmp.add_component(block1, transform)
-        .expect("Could not add component");
-
-    // Block 2
-    let model = MeshComponent { id: cube_id };
-    let transform = Transform::new(glm::Vec3::new(1.0, 2.0, 1.0), None, None);
-    let block2 = tmp.create_entity().expect("Could not create entity");
-    tmp.add_component(block2, model)
-        .expect("Could not add component");
-    tmp.add_component(block2, transform)
-        .expect("Could not add component");
-
-    // TODO: Figure out why this block does not collide (maybe rotation).
-    // Falling Block
-    let model = MeshComponent { id: cube_id };
-    let transform = Transform::new(
-        glm::Vec3::new(0.0, 5.0, 0.0),
-        Some(glm::Vec4::new(45.0, 0.0, 1.0, 0.0)),
-        None,
-    );
-    let rigid_body = RigidBody::default();
-    let gravity = GravityComponent {
-        force: glm::Vec3::new(0.0, -0.001, 0.0),
-    };
-    let falling_block = tmp.create_entity().expect("Could not create entity");
-    tmp.add_component(falling_block, model)
-        .expect("Could not add component");
-    tmp.add_component(falling_block, transform)
-        .expect("Could not add component");
-    tmp.add_component(falling_block, rigid_body)
-        .expect("Could not add component");
-    tmp.add_component(falling_block, gravity)
-        .expect("Could not add component");
-
-    let transform = Transform::new(glm::Vec3::new(-1.0, 4.0, 0.0), None, None);
-    let controlled = Controllable::new();
-    let rigid_body = RigidBody::new(1.85, 0.5);
-    let gravity = GravityComponent {
-        force: glm::Vec3::new(0.0, -0.001, 0.0),
-    };
-    let camera_followable = CameraFollowable::new(true, glm::Vec3::new(0.0, 1.0, 0.0));
-    let player = tmp.create_entity().expect("Could not create entity");
-    tmp.add_component(player, transform)
-        .expect("Could not add component");
-    tmp.add_component(player, controlled)
-        .expect("Could not add component");
-    tmp.add_component(player, rigid_body)
-        .expect("Could not add component");
-    tmp.add_component(player, gravity)
-        .expect("Could not add component");
-    tmp.add_component(player, camera_followable)
-        .expect("Could not add component");
-
-    let mut collider = Collider::new();
-
-    collider.add_collidable(Transform::new(
-        glm::Vec3::zeros(),
-        None,
-        Some(glm::Vec3::new(101.0, 0.01, 101.0)),
-    ));
-
-    // Render System
-    let mut render_system = RenderSystem::init(&ecs, &mesh_manager, &shader);
+    // Controller System
+    let event_pump = sdl.event_pump().unwrap();
+    schedule
+        .add_system(
+            Stage::Input,
+            "controller",
+            ControllerSystem::init(event_pump),
+        )
+        .before("physics");
 
     // Physics System
-    let mut physics_system = PhysicsSystem::init(&ecs, &mut collider);
+    schedule.add_parallel_system(Stage::FixedUpdate, "physics", PhysicsSystem::init());
 
-    let event_pump = sdl.event_pump().unwrap();
-    // Controller System
-    let mut controller_system = ControllerSystem::init(&ecs, event_pump);
+    // Transform System
+    schedule.add_parallel_system(Stage::PostPhysics, "transform", TransformSystem::init());
 
-    let start_time = std::time::Instant::now();
-    let mut tick_count: u32 = 0;
-    let mut last_tick_ms: f32 = start_time.elapsed().as_secs_f32() * 1000.0;
+    // Render System
+    schedule.add_system(Stage::Render, "render", RenderSystem::init(&shader));
 
-    drop(tmp);
+    let start_time = std::time::Instant::now();
+    let mut game_loop = GameLoop::new();
 
     'main: loop {
-        let current_time_ms = start_time.elapsed().as_secs_f32() * 1000.0;
+        let ticks = game_loop.advance(&ecs).expect("Could not get time");
+
+        // TICK - fixed update, as often as needed to catch up with the clock
+        for _ in 0..ticks {
+            // Events live for two ticks, so readers in fixed update never miss any.
+            ecs.update_events();
 
-        // TICK - fixed update
-        if current_time_ms >= last_tick_ms + TICK_RATE {
             // WARN: Controls should probably be processed every frame, then physics applied in
             // fixed update
-            match controller_system.update() {
-                Ok(_) => (),
-                Err(e) => match e {
-                    SystemError::RequestedQuit => break 'main,
-                    _ => panic!("Could not update controller system"),
-                },
-            };
-
-            physics_system
-                .update()
-                .expect("Could not update physics system.");
-
-            // update tick info
-            tick_count += 1;
-            last_tick_ms = current_time_ms;
+            for stage in [Stage::Input, Stage::FixedUpdate, Stage::PostPhysics] {
+                match schedule.run_stage(stage, &mut ecs) {
+                    Ok(_) => (),
+                    Err(e) if e.requested_quit() => break 'main,
+                    Err(e) => panic!("Could not update systems: {:?}", e),
+                };
+            }
         }
 
         // render
@@ -212,17 +244,31 @@ fn main() {
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
 
-        render_system
-            .update()
+        schedule
+            .run_stage(Stage::Render, &mut ecs)
             .expect("Couldn't update render system");
 
         window.gl_swap_window();
+
+        ecs.clear_trackers();
     }
 
     let total_run_time = start_time.elapsed().as_secs_f32();
+    let tick_count = ecs
+        .resource::<Time>()
+        .expect("Could not get time")
+        .tick_count();
     let average_tick_rate = tick_count as f32 / total_run_time;
     println!(
         "Ran for {}s with {} ticks for a tick rate of {} per second",
         total_run_time, tick_count, average_tick_rate
     );
+
+    let memory_usage = ecs.memory_usage().expect("Could not measure ECS memory");
+    println!(
+        "{} entities using {} bytes, {} bytes of components",
+        memory_usage.entity_count,
+        memory_usage.entity_bytes,
+        memory_usage.component_bytes()
+    );
 }
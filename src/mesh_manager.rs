@@ -1,10 +1,12 @@
 use crate::mesh::Mesh;
 use std::collections::HashMap;
 
-type MeshId = u32;
+pub type MeshId = u32;
 
 pub struct MeshManager {
     meshes: HashMap<MeshId, Mesh>,
+    names: HashMap<MeshId, String>,
+    ids: HashMap<String, MeshId>,
     mesh_count: usize,
 }
 
@@ -12,13 +14,26 @@ impl MeshManager {
     pub fn new() -> Self {
         Self {
             meshes: HashMap::new(),
+            names: HashMap::new(),
+            ids: HashMap::new(),
             mesh_count: 0,
         }
     }
 
-    pub fn add_mesh(&mut self, mesh: Mesh) -> MeshId {
-        let mesh_id = self.mesh_count as u32;
+    /// Stores a mesh under an asset name. Ids depend on the order meshes are added, so saves
+    /// refer to meshes by name instead.
+    pub fn add_mesh(&mut self, name: &str, mesh: Mesh) -> MeshId {
+        let mesh_id = self.reserve_mesh(name);
         self.meshes.insert(mesh_id, mesh);
+
+        mesh_id
+    }
+
+    /// Hands out the id for an asset name without storing a mesh under it yet.
+    pub fn reserve_mesh(&mut self, name: &str) -> MeshId {
+        let mesh_id = self.mesh_count as u32;
+        self.names.insert(mesh_id, name.to_string());
+        self.ids.insert(name.to_string(), mesh_id);
         self.mesh_count += 1;
 
         mesh_id
@@ -27,4 +42,12 @@ impl MeshManager {
     pub fn get_mesh(&self, mesh_id: MeshId) -> Option<&Mesh> {
         self.meshes.get(&mesh_id)
     }
+
+    pub fn mesh_name(&self, mesh_id: MeshId) -> Option<&str> {
+        self.names.get(&mesh_id).map(String::as_str)
+    }
+
+    pub fn mesh_id(&self, name: &str) -> Option<MeshId> {
+        self.ids.get(name).copied()
+    }
 }
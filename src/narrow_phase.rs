@@ -0,0 +1,790 @@
+use crate::{
+    broadphase::Bounds,
+    geometry::{ray_aabb, ray_capsule, ray_sphere, ray_triangle, RayHit},
+    ray::Ray,
+};
+use nalgebra_glm::{self as glm, Mat3, Vec3};
+
+/// Lengths below this are treated as zero when normalizing.
+const EPSILON: f32 = 1e-6;
+
+/// Steps of the ternary searches along capsule segments, enough to get below `f32` precision.
+const SEGMENT_SEARCH_STEPS: usize = 48;
+
+/// Overlap between two shapes. `normal` points from the first shape towards the second, so
+/// moving the first one by `-normal * depth` separates them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub depth: f32,
+}
+
+impl Contact {
+    fn flipped(self) -> Self {
+        Self {
+            normal: -self.normal,
+            ..self
+        }
+    }
+}
+
+/// A collider shape in world space, see `ColliderShape::placed`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlacedShape {
+    /// Box around `center` along the unit-length columns of `axes`.
+    Box {
+        center: Vec3,
+        axes: Mat3,
+        half_extents: Vec3,
+    },
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    /// Every point within `radius` of the segment from `start` to `end`.
+    Capsule {
+        start: Vec3,
+        end: Vec3,
+        radius: f32,
+    },
+    TriangleMesh(Vec<[Vec3; 3]>),
+}
+
+impl PlacedShape {
+    /// Order in which pairs are handled, each pair is only implemented one way round.
+    fn rank(&self) -> u8 {
+        match self {
+            PlacedShape::Box { .. } => 0,
+            PlacedShape::Sphere { .. } => 1,
+            PlacedShape::Capsule { .. } => 2,
+            PlacedShape::TriangleMesh(_) => 3,
+        }
+    }
+
+    /// The same shape moved by `offset`.
+    pub fn translated(&self, offset: Vec3) -> Self {
+        match self {
+            PlacedShape::Box {
+                center,
+                axes,
+                half_extents,
+            } => PlacedShape::Box {
+                center: center + offset,
+                axes: *axes,
+                half_extents: *half_extents,
+            },
+            PlacedShape::Sphere { center, radius } => PlacedShape::Sphere {
+                center: center + offset,
+                radius: *radius,
+            },
+            PlacedShape::Capsule { start, end, radius } => PlacedShape::Capsule {
+                start: start + offset,
+                end: end + offset,
+                radius: *radius,
+            },
+            PlacedShape::TriangleMesh(triangles) => PlacedShape::TriangleMesh(
+                triangles
+                    .iter()
+                    .map(|triangle| triangle.map(|vertex| vertex + offset))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The world-axis-aligned box around the shape.
+    pub fn bounds(&self) -> Bounds {
+        match self {
+            PlacedShape::Box {
+                center,
+                axes,
+                half_extents,
+            } => {
+                let reach = axes.abs() * half_extents;
+                Bounds::new(center - reach, center + reach)
+            }
+            PlacedShape::Sphere { center, radius } => {
+                let reach = Vec3::repeat(*radius);
+                Bounds::new(center - reach, center + reach)
+            }
+            PlacedShape::Capsule { start, end, radius } => {
+                let reach = Vec3::repeat(*radius);
+                Bounds::new(glm::min2(start, end) - reach, glm::max2(start, end) + reach)
+            }
+            PlacedShape::TriangleMesh(triangles) => {
+                Bounds::around(triangles.iter().flatten().copied())
+            }
+        }
+    }
+
+    /// Smallest width of the shape in any direction, zero for triangle meshes.
+    pub fn thickness(&self) -> f32 {
+        match self {
+            PlacedShape::Box { half_extents, .. } => half_extents.min() * 2.0,
+            PlacedShape::Sphere { radius, .. } | PlacedShape::Capsule { radius, .. } => {
+                radius * 2.0
+            }
+            PlacedShape::TriangleMesh(_) => 0.0,
+        }
+    }
+
+    /// Where the ray first hits the shape, see [`RayHit`].
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        match self {
+            PlacedShape::Box {
+                center,
+                axes,
+                half_extents,
+            } => {
+                // Cast in the box's own space, where it is axis aligned.
+                let local = Ray::new(
+                    axes.transpose() * (ray.origin() - center),
+                    axes.transpose() * ray.direction(),
+                );
+                let hit = ray_aabb(&local, -half_extents, *half_extents)?;
+
+                Some(RayHit {
+                    point: ray.at(hit.distance),
+                    normal: axes * hit.normal,
+                    ..hit
+                })
+            }
+            PlacedShape::Sphere { center, radius } => ray_sphere(ray, *center, *radius),
+            PlacedShape::Capsule { start, end, radius } => ray_capsule(ray, *start, *end, *radius),
+            PlacedShape::TriangleMesh(triangles) => triangles
+                .iter()
+                .filter_map(|triangle| ray_triangle(ray, triangle))
+                .min_by(|a, b| a.distance.total_cmp(&b.distance)),
+        }
+    }
+}
+
+/// How `a` and `b` overlap, if they do. Triangle meshes are static level geometry, so two of
+/// them never touch.
+pub fn contact(a: &PlacedShape, b: &PlacedShape) -> Option<Contact> {
+    if a.rank() > b.rank() {
+        return contact(b, a).map(Contact::flipped);
+    }
+
+    match (a, b) {
+        (
+            PlacedShape::Box {
+                center,
+                axes,
+                half_extents,
+            },
+            _,
+        ) => box_contact(*center, axes, *half_extents, b),
+        (
+            PlacedShape::Sphere {
+                center: center_a,
+                radius: radius_a,
+            },
+            PlacedShape::Sphere {
+                center: center_b,
+                radius: radius_b,
+            },
+        ) => sphere_sphere(*center_a, *radius_a, *center_b, *radius_b),
+        (
+            PlacedShape::Sphere { center, radius },
+            PlacedShape::Capsule {
+                start,
+                end,
+                radius: capsule_radius,
+            },
+        ) => {
+            let closest = closest_point_on_segment(*center, *start, *end);
+            sphere_sphere(*center, *radius, closest, *capsule_radius)
+        }
+        (PlacedShape::Sphere { center, radius }, PlacedShape::TriangleMesh(triangles)) => {
+            deepest(triangles, |triangle| {
+                sphere_triangle(*center, *radius, triangle)
+            })
+        }
+        (
+            PlacedShape::Capsule {
+                start: start_a,
+                end: end_a,
+                radius: radius_a,
+            },
+            PlacedShape::Capsule {
+                start: start_b,
+                end: end_b,
+                radius: radius_b,
+            },
+        ) => {
+            let point_a = minimize_along_segment(*start_a, *end_a, |point| {
+                (point - closest_point_on_segment(point, *start_b, *end_b)).norm()
+            });
+            let point_b = closest_point_on_segment(point_a, *start_b, *end_b);
+            sphere_sphere(point_a, *radius_a, point_b, *radius_b)
+        }
+        (PlacedShape::Capsule { start, end, radius }, PlacedShape::TriangleMesh(triangles)) => {
+            deepest(triangles, |triangle| {
+                capsule_triangle(*start, *end, *radius, triangle)
+            })
+        }
+        (PlacedShape::TriangleMesh(_), PlacedShape::TriangleMesh(_)) => None,
+        _ => unreachable!("Shape pairs are ordered by rank"),
+    }
+}
+
+fn box_contact(
+    center: Vec3,
+    axes: &Mat3,
+    half_extents: Vec3,
+    other: &PlacedShape,
+) -> Option<Contact> {
+    match other {
+        PlacedShape::Box {
+            center: other_center,
+            axes: other_axes,
+            half_extents: other_half_extents,
+        } => box_box(
+            center,
+            axes,
+            half_extents,
+            *other_center,
+            other_axes,
+            *other_half_extents,
+        ),
+        PlacedShape::Sphere {
+            center: sphere_center,
+            radius,
+        } => box_sphere(center, axes, half_extents, *sphere_center, *radius),
+        PlacedShape::Capsule { start, end, radius } => {
+            let point = minimize_along_segment(*start, *end, |point| {
+                signed_distance_to_box(center, axes, half_extents, point)
+            });
+            box_sphere(center, axes, half_extents, point, *radius)
+        }
+        PlacedShape::TriangleMesh(triangles) => deepest(triangles, |triangle| {
+            box_triangle(center, axes, half_extents, triangle)
+        }),
+    }
+}
+
+/// The deepest contact with any of the triangles.
+fn deepest(
+    triangles: &[[Vec3; 3]],
+    contact: impl Fn(&[Vec3; 3]) -> Option<Contact>,
+) -> Option<Contact> {
+    triangles
+        .iter()
+        .filter_map(contact)
+        .max_by(|a, b| a.depth.total_cmp(&b.depth))
+}
+
+fn sphere_sphere(center_a: Vec3, radius_a: f32, center_b: Vec3, radius_b: f32) -> Option<Contact> {
+    let offset = center_b - center_a;
+    let distance = offset.norm();
+    if distance >= radius_a + radius_b {
+        return None;
+    }
+
+    // Concentric spheres have no preferred direction, so they are pushed apart vertically.
+    let normal = offset.try_normalize(EPSILON).unwrap_or_else(Vec3::y);
+    let depth = radius_a + radius_b - distance;
+
+    Some(Contact {
+        point: center_a + normal * (radius_a - depth / 2.0),
+        normal,
+        depth,
+    })
+}
+
+fn box_sphere(
+    center: Vec3,
+    axes: &Mat3,
+    half_extents: Vec3,
+    sphere_center: Vec3,
+    radius: f32,
+) -> Option<Contact> {
+    let local = axes.transpose() * (sphere_center - center);
+    let clamped = local.zip_map(&half_extents, |value, half| value.clamp(-half, half));
+    let offset = local - clamped;
+    let distance = offset.norm();
+
+    if distance > EPSILON {
+        if distance >= radius {
+            return None;
+        }
+
+        return Some(Contact {
+            point: center + axes * clamped,
+            normal: axes * (offset / distance),
+            depth: radius - distance,
+        });
+    }
+
+    // The sphere's center is inside the box, push it out through the nearest face.
+    let (axis, inside) = (0..3)
+        .map(|axis| (axis, half_extents[axis] - local[axis].abs()))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("A box has three axes");
+    let sign = if local[axis] < 0.0 { -1.0 } else { 1.0 };
+
+    Some(Contact {
+        point: sphere_center,
+        normal: axes.column(axis) * sign,
+        depth: radius + inside,
+    })
+}
+
+/// Distance from the box's surface, negative inside the box.
+fn signed_distance_to_box(center: Vec3, axes: &Mat3, half_extents: Vec3, point: Vec3) -> f32 {
+    let local = axes.transpose() * (point - center);
+    let outside = local.abs() - half_extents;
+    let inside = outside.max().min(0.0);
+
+    outside.map(|value| value.max(0.0)).norm() + inside
+}
+
+fn closest_point_on_box(center: Vec3, axes: &Mat3, half_extents: Vec3, point: Vec3) -> Vec3 {
+    let local = axes.transpose() * (point - center);
+
+    center + axes * local.zip_map(&half_extents, |value, half| value.clamp(-half, half))
+}
+
+fn project_box(center: Vec3, axes: &Mat3, half_extents: Vec3, axis: &Vec3) -> (f32, f32) {
+    let middle = center.dot(axis);
+    let reach: f32 = (0..3)
+        .map(|index| half_extents[index] * axes.column(index).dot(axis).abs())
+        .sum();
+
+    (middle - reach, middle + reach)
+}
+
+fn project_triangle(triangle: &[Vec3; 3], axis: &Vec3) -> (f32, f32) {
+    triangle
+        .iter()
+        .map(|vertex| vertex.dot(axis))
+        .fold((f32::MAX, f32::MIN), |(min, max), value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+/// Separating axis test. Returns the axis of least overlap, pointing from `a` towards `b`, and
+/// the overlap along it, or `None` if any of the axes separates the shapes.
+fn least_overlap(
+    axes: impl IntoIterator<Item = Vec3>,
+    project_a: impl Fn(&Vec3) -> (f32, f32),
+    project_b: impl Fn(&Vec3) -> (f32, f32),
+) -> Option<(Vec3, f32)> {
+    let mut least: Option<(Vec3, f32)> = None;
+
+    // Axes from crossing parallel edges are degenerate and skipped.
+    for axis in axes
+        .into_iter()
+        .filter_map(|axis| axis.try_normalize(EPSILON))
+    {
+        let (min_a, max_a) = project_a(&axis);
+        let (min_b, max_b) = project_b(&axis);
+        let forward = max_a - min_b;
+        let backward = max_b - min_a;
+        let overlap = forward.min(backward);
+        if overlap <= 0.0 {
+            return None;
+        }
+
+        if !least.is_some_and(|(_, least_overlap)| least_overlap <= overlap) {
+            let normal = if forward < backward { axis } else { -axis };
+            least = Some((normal, overlap));
+        }
+    }
+
+    least
+}
+
+fn box_box(
+    center_a: Vec3,
+    axes_a: &Mat3,
+    half_extents_a: Vec3,
+    center_b: Vec3,
+    axes_b: &Mat3,
+    half_extents_b: Vec3,
+) -> Option<Contact> {
+    let face_axes =
+        (0..3).flat_map(|index| [axes_a.column(index).into(), axes_b.column(index).into()]);
+    let edge_axes =
+        (0..3).flat_map(|a| (0..3).map(move |b| axes_a.column(a).cross(&axes_b.column(b))));
+
+    let (normal, depth) = least_overlap(
+        face_axes.chain(edge_axes),
+        |axis| project_box(center_a, axes_a, half_extents_a, axis),
+        |axis| project_box(center_b, axes_b, half_extents_b, axis),
+    )?;
+
+    let on_a = closest_point_on_box(center_a, axes_a, half_extents_a, center_b);
+    let on_b = closest_point_on_box(center_b, axes_b, half_extents_b, center_a);
+
+    Some(Contact {
+        point: (on_a + on_b) / 2.0,
+        normal,
+        depth,
+    })
+}
+
+fn box_triangle(
+    center: Vec3,
+    axes: &Mat3,
+    half_extents: Vec3,
+    triangle: &[Vec3; 3],
+) -> Option<Contact> {
+    let [a, b, c] = *triangle;
+    let edges = [b - a, c - b, a - c];
+    let face_normal = edges[0].cross(&edges[1]);
+
+    let box_axes = (0..3).map(|index| axes.column(index).into());
+    let edge_axes = (0..3).flat_map(|index| edges.map(|edge| axes.column(index).cross(&edge)));
+
+    let (normal, depth) = least_overlap(
+        box_axes.chain([face_normal]).chain(edge_axes),
+        |axis| project_box(center, axes, half_extents, axis),
+        |axis| project_triangle(triangle, axis),
+    )?;
+
+    Some(Contact {
+        point: closest_point_on_triangle(center, triangle),
+        normal,
+        depth,
+    })
+}
+
+fn sphere_triangle(center: Vec3, radius: f32, triangle: &[Vec3; 3]) -> Option<Contact> {
+    let closest = closest_point_on_triangle(center, triangle);
+    let offset = closest - center;
+    let distance = offset.norm();
+    if distance >= radius {
+        return None;
+    }
+
+    // A center lying on the triangle is pushed out against its winding.
+    let normal = offset
+        .try_normalize(EPSILON)
+        .unwrap_or_else(|| -triangle_normal(triangle));
+
+    Some(Contact {
+        point: closest,
+        normal,
+        depth: radius - distance,
+    })
+}
+
+fn capsule_triangle(start: Vec3, end: Vec3, radius: f32, triangle: &[Vec3; 3]) -> Option<Contact> {
+    let point = minimize_along_segment(start, end, |point| {
+        (point - closest_point_on_triangle(point, triangle)).norm()
+    });
+    let closest = closest_point_on_triangle(point, triangle);
+    if (closest - point).norm() > EPSILON {
+        return sphere_triangle(point, radius, triangle);
+    }
+
+    // The segment passes through the triangle. Push the capsule back to the side its middle
+    // is on, far enough for the end poking through to clear the triangle as well.
+    let face_normal = triangle_normal(triangle);
+    let middle = (start + end) / 2.0;
+    let outward = if (middle - triangle[0]).dot(&face_normal) < 0.0 {
+        -face_normal
+    } else {
+        face_normal
+    };
+    let behind = [start, end]
+        .iter()
+        .map(|point| -(point - triangle[0]).dot(&outward))
+        .fold(0.0, f32::max);
+
+    Some(Contact {
+        point: closest,
+        normal: -outward,
+        depth: radius + behind,
+    })
+}
+
+fn triangle_normal(triangle: &[Vec3; 3]) -> Vec3 {
+    let [a, b, c] = *triangle;
+
+    (b - a)
+        .cross(&(c - a))
+        .try_normalize(EPSILON)
+        .unwrap_or_else(Vec3::y)
+}
+
+fn closest_point_on_segment(point: Vec3, start: Vec3, end: Vec3) -> Vec3 {
+    let segment = end - start;
+    let length_squared = segment.norm_squared();
+    if length_squared <= EPSILON * EPSILON {
+        return start;
+    }
+
+    let t = ((point - start).dot(&segment) / length_squared).clamp(0.0, 1.0);
+    start + segment * t
+}
+
+/// The point on the segment where the convex function `distance` is smallest.
+fn minimize_along_segment(start: Vec3, end: Vec3, distance: impl Fn(Vec3) -> f32) -> Vec3 {
+    let at = |t: f32| start + (end - start) * t;
+    let (mut low, mut high) = (0.0_f32, 1.0_f32);
+
+    for _ in 0..SEGMENT_SEARCH_STEPS {
+        let left = low + (high - low) / 3.0;
+        let right = high - (high - low) / 3.0;
+        if distance(at(left)) <= distance(at(right)) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+
+    at((low + high) / 2.0)
+}
+
+/// Closest point on a triangle by the region it falls in, see Ericson's Real-Time Collision
+/// Detection, section 5.1.5.
+fn closest_point_on_triangle(point: Vec3, triangle: &[Vec3; 3]) -> Vec3 {
+    let [a, b, c] = *triangle;
+    let ab = b - a;
+    let ac = c - a;
+
+    let ap = point - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = point - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = point - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denominator = 1.0 / (va + vb + vc);
+    a + ab * (vb * denominator) + ac * (vc * denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Allowed error, loose enough for the searches along capsule segments.
+    const TOLERANCE: f32 = 1e-3;
+
+    fn cuboid(center: Vec3, half_extents: Vec3) -> PlacedShape {
+        PlacedShape::Box {
+            center,
+            axes: Mat3::identity(),
+            half_extents,
+        }
+    }
+
+    fn cube(x: f32, y: f32, z: f32) -> PlacedShape {
+        cuboid(Vec3::new(x, y, z), Vec3::repeat(0.5))
+    }
+
+    fn sphere(x: f32, y: f32, z: f32) -> PlacedShape {
+        PlacedShape::Sphere {
+            center: Vec3::new(x, y, z),
+            radius: 0.5,
+        }
+    }
+
+    fn capsule(start: Vec3, end: Vec3) -> PlacedShape {
+        PlacedShape::Capsule {
+            start,
+            end,
+            radius: 0.5,
+        }
+    }
+
+    /// Capsule standing upright from `y = -1` to `y = 1`.
+    fn upright_capsule(x: f32) -> PlacedShape {
+        capsule(Vec3::new(x, -1.0, 0.0), Vec3::new(x, 1.0, 0.0))
+    }
+
+    /// Ten by ten square at `y = 0` facing up, split along its diagonal.
+    fn floor() -> PlacedShape {
+        let corner = |x: f32, z: f32| Vec3::new(x, 0.0, z);
+        PlacedShape::TriangleMesh(vec![
+            [corner(-5.0, -5.0), corner(-5.0, 5.0), corner(5.0, 5.0)],
+            [corner(-5.0, -5.0), corner(5.0, 5.0), corner(5.0, -5.0)],
+        ])
+    }
+
+    /// Checks the contact both ways round, the normal flipping with the order.
+    fn assert_contact(a: &PlacedShape, b: &PlacedShape, normal: Vec3, depth: f32) {
+        for (first, second, normal) in [(a, b, normal), (b, a, -normal)] {
+            let contact = contact(first, second)
+                .unwrap_or_else(|| panic!("{:?} should touch {:?}", first, second));
+            assert!(
+                (contact.normal - normal).norm() < TOLERANCE,
+                "{:?} against {:?}: normal {:?}, expected {:?}",
+                first,
+                second,
+                contact.normal,
+                normal
+            );
+            assert!(
+                (contact.depth - depth).abs() < TOLERANCE,
+                "{:?} against {:?}: depth {}, expected {}",
+                first,
+                second,
+                contact.depth,
+                depth
+            );
+        }
+    }
+
+    fn assert_apart(a: &PlacedShape, b: &PlacedShape) {
+        assert_eq!(contact(a, b), None);
+        assert_eq!(contact(b, a), None);
+    }
+
+    #[test]
+    fn box_and_box() {
+        let a = cube(0.0, 0.0, 0.0);
+
+        assert_contact(&a, &cube(0.9, 0.0, 0.0), Vec3::x(), 0.1);
+        assert_apart(&a, &cube(1.1, 0.0, 0.0));
+        assert_contact(&a, &cube(0.0, -0.2, 0.0), -Vec3::y(), 0.8);
+
+        // Turned 45 degrees, so a corner pokes into the side.
+        let turned = PlacedShape::Box {
+            center: Vec3::new(0.5 + 0.5 * 2.0_f32.sqrt() - 0.1, 0.0, 0.0),
+            axes: glm::mat4_to_mat3(&glm::rotation(std::f32::consts::FRAC_PI_4, &Vec3::z())),
+            half_extents: Vec3::repeat(0.5),
+        };
+        assert_contact(&a, &turned, Vec3::x(), 0.1);
+    }
+
+    #[test]
+    fn box_and_sphere() {
+        let a = cube(0.0, 0.0, 0.0);
+
+        assert_contact(&a, &sphere(0.9, 0.0, 0.0), Vec3::x(), 0.1);
+        assert_apart(&a, &sphere(1.1, 0.0, 0.0));
+        // The center is inside, so the sphere goes out through the nearest face.
+        assert_contact(&a, &sphere(0.3, 0.0, 0.0), Vec3::x(), 0.7);
+    }
+
+    #[test]
+    fn box_and_capsule() {
+        let a = cuboid(Vec3::zeros(), Vec3::new(0.5, 2.0, 2.0));
+
+        assert_contact(&a, &upright_capsule(0.9), Vec3::x(), 0.1);
+        assert_apart(&a, &upright_capsule(1.1));
+        assert_contact(&a, &upright_capsule(0.3), Vec3::x(), 0.7);
+    }
+
+    #[test]
+    fn box_and_mesh() {
+        let floor = floor();
+
+        assert_contact(&cube(1.0, 0.4, -2.0), &floor, -Vec3::y(), 0.1);
+        assert_apart(&cube(1.0, 0.6, -2.0), &floor);
+        assert_contact(&cube(1.0, 0.1, -2.0), &floor, -Vec3::y(), 0.4);
+    }
+
+    #[test]
+    fn sphere_and_sphere() {
+        let a = sphere(0.0, 0.0, 0.0);
+
+        assert_contact(&a, &sphere(0.9, 0.0, 0.0), Vec3::x(), 0.1);
+        assert_apart(&a, &sphere(1.1, 0.0, 0.0));
+        assert_contact(&a, &sphere(0.2, 0.0, 0.0), Vec3::x(), 0.8);
+
+        // Concentric spheres are pushed apart vertically, the first one downwards.
+        let contact = contact(&a, &a).expect("Concentric spheres should touch");
+        assert_eq!(contact.normal, Vec3::y());
+        assert_eq!(contact.depth, 1.0);
+    }
+
+    #[test]
+    fn sphere_and_capsule() {
+        let a = sphere(0.0, 0.0, 0.0);
+
+        assert_contact(&a, &upright_capsule(0.9), Vec3::x(), 0.1);
+        assert_apart(&a, &upright_capsule(1.1));
+        assert_contact(&a, &upright_capsule(0.2), Vec3::x(), 0.8);
+    }
+
+    #[test]
+    fn sphere_and_mesh() {
+        let floor = floor();
+
+        assert_contact(&sphere(1.0, 0.4, -2.0), &floor, -Vec3::y(), 0.1);
+        assert_apart(&sphere(1.0, 0.6, -2.0), &floor);
+        assert_contact(&sphere(1.0, 0.1, -2.0), &floor, -Vec3::y(), 0.4);
+        // Centered on the floor, the sphere goes back out the front.
+        assert_contact(&sphere(1.0, 0.0, -2.0), &floor, -Vec3::y(), 0.5);
+    }
+
+    #[test]
+    fn capsule_and_capsule() {
+        let a = upright_capsule(0.0);
+
+        assert_contact(&a, &upright_capsule(0.9), Vec3::x(), 0.1);
+        assert_apart(&a, &upright_capsule(1.1));
+        let crossing = capsule(Vec3::new(0.2, 0.0, -1.0), Vec3::new(0.2, 0.0, 1.0));
+        assert_contact(&a, &crossing, Vec3::x(), 0.8);
+    }
+
+    #[test]
+    fn capsule_and_mesh() {
+        let floor = floor();
+        let lying = |y: f32| capsule(Vec3::new(0.0, y, -2.0), Vec3::new(2.0, y, -2.0));
+
+        assert_contact(&lying(0.4), &floor, -Vec3::y(), 0.1);
+        assert_apart(&lying(0.6), &floor);
+        // Poking through, the capsule goes back up until its lower end clears the floor.
+        let poking = capsule(Vec3::new(1.0, -0.3, -2.0), Vec3::new(1.0, 1.0, -2.0));
+        assert_contact(&poking, &floor, -Vec3::y(), 0.8);
+    }
+
+    #[test]
+    fn meshes_never_touch() {
+        assert_eq!(contact(&floor(), &floor()), None);
+    }
+
+    #[test]
+    fn swapped_pairs_get_the_flipped_contact() {
+        let floor = floor();
+        let pairs = [
+            (sphere(0.0, 0.0, 0.0), cube(0.9, 0.0, 0.0)),
+            (upright_capsule(0.0), cube(0.9, 0.0, 0.0)),
+            (upright_capsule(0.0), sphere(0.9, 0.0, 0.0)),
+            (floor.clone(), cube(1.0, 0.4, -2.0)),
+            (floor.clone(), sphere(1.0, 0.4, -2.0)),
+            (floor, upright_capsule(1.0)),
+        ];
+
+        for (a, b) in pairs.iter() {
+            assert!(a.rank() > b.rank());
+            let swapped = contact(b, a).expect("Pairs should touch");
+            assert_eq!(contact(a, b), Some(swapped.flipped()));
+        }
+    }
+}
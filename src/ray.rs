@@ -1,10 +1,5 @@
 use nalgebra_glm::Vec3;
 
-pub struct Intersection {
-    pub distance: f32,
-    pub point: Vec3,
-}
-
 pub struct Ray {
     origin: Vec3,
     direction: Vec3,
@@ -15,14 +10,16 @@ impl Ray {
         Self { origin, direction }
     }
 
-    pub fn intersects(&self, plane_normal: &Vec3) -> Option<Intersection> {
-        let denominator = self.direction.dot(plane_normal);
-        if denominator == 0.0 {
-            None
-        } else {
-            let distance = -(self.origin.dot(plane_normal)) / denominator;
-            let point = self.origin + distance * self.direction;
-            Some(Intersection { distance, point })
-        }
+    pub fn origin(&self) -> Vec3 {
+        self.origin
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    /// The point `distance` lengths of the direction along the ray.
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + distance * self.direction
     }
 }
@@ -0,0 +1,52 @@
+use std::any::{type_name, TypeId};
+
+/// The components and resources a system reads and writes. Two systems whose accesses do not
+/// conflict may run at the same time.
+#[derive(Clone, Debug, Default)]
+pub struct SystemAccess {
+    reads: Vec<(TypeId, &'static str)>,
+    writes: Vec<(TypeId, &'static str)>,
+    exclusive: bool,
+}
+
+impl SystemAccess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Access to the whole world. Conflicts with every other system.
+    pub fn exclusive() -> Self {
+        Self {
+            exclusive: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn read<T: 'static>(mut self) -> Self {
+        self.reads.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    pub fn write<T: 'static>(mut self) -> Self {
+        self.writes.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    fn writes_any(&self, accesses: &[(TypeId, &'static str)]) -> bool {
+        self.writes
+            .iter()
+            .any(|(write, _)| accesses.iter().any(|(other, _)| write == other))
+    }
+
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        self.exclusive
+            || other.exclusive
+            || self.writes_any(&other.reads)
+            || self.writes_any(&other.writes)
+            || other.writes_any(&self.reads)
+    }
+}
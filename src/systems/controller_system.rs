@@ -1,32 +1,38 @@
-use super::{System, SystemError};
+use super::{System, SystemAccess, SystemContext, SystemError};
 use crate::{
     components::{controllable::Controllable, rigid_body::RigidBody},
     constants::PLAYER_MOVE_SPEED,
-    ecs::Ecs,
+    ecs::Events,
+    events::JumpPressedEvent,
     utils::flatten_vector,
 };
 use sdl2::{event::Event, keyboard::Keycode, EventPump};
-use std::sync::Mutex;
 
-pub struct ControllerSystem<'a> {
-    ecs: &'a Mutex<Ecs>,
+pub struct ControllerSystem {
     event_pump: EventPump,
 }
 
-impl<'a> ControllerSystem<'a> {
-    pub fn init(ecs: &'a Mutex<Ecs>, event_pump: EventPump) -> Self {
-        Self { ecs, event_pump }
+impl ControllerSystem {
+    pub fn init(event_pump: EventPump) -> Self {
+        Self { event_pump }
     }
 }
 
-impl<'a> System for ControllerSystem<'a> {
-    fn update(&mut self) -> Result<(), SystemError> {
-        let ecs = self.ecs.lock().expect("Could not lock ECS");
+impl System for ControllerSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .write::<Controllable>()
+            .write::<RigidBody>()
+            .write::<Events<JumpPressedEvent>>()
+    }
 
+    fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+        let ecs = context.ecs;
         let mut forward_motion: f32 = 0.0;
         let mut horizontal_motion: f32 = 0.0;
         let mut rotate_x = 0.0;
         let mut rotate_y = 0.0;
+        let mut jump_pressed = false;
 
         // Poll events
         for event in self.event_pump.poll_iter() {
@@ -88,6 +94,9 @@ impl<'a> System for ControllerSystem<'a> {
                             Keycode::D => {
                                 horizontal_motion += 1.0;
                             }
+                            Keycode::Space => {
+                                jump_pressed = true;
+                            }
                             Keycode::Escape => {
                                 return Err(SystemError::RequestedQuit);
                             }
@@ -129,21 +138,18 @@ impl<'a> System for ControllerSystem<'a> {
             }
         }
 
-        let mut controllables = ecs
-            .get_component_vec::<Controllable>()
-            .expect("Could not get controlled vector");
-        let mut rigid_body = ecs
-            .get_component_vec::<RigidBody>()
-            .expect("Could not get rigid body vector");
+        let mut query = ecs
+            .query::<(&mut Controllable, &mut RigidBody)>()
+            .expect("Could not query controllables");
+        let mut jump_events = ecs
+            .event_writer::<JumpPressedEvent>()
+            .expect("Could not get jump events");
 
-        let union = controllables
-            .iter_mut()
-            .zip(rigid_body.iter_mut())
-            .filter_map(|(controlled, rigid_body)| {
-                Some((controlled.as_mut()?, rigid_body.as_mut()?))
-            });
+        for (entity, (mut controlled, mut rigid_body)) in query.iter() {
+            if jump_pressed {
+                jump_events.send(JumpPressedEvent { entity });
+            }
 
-        for (controlled, rigid_body) in union {
             controlled.rotate(rotate_x, rotate_y);
             controlled.apply_motion(forward_motion, horizontal_motion);
 
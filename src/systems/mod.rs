@@ -1,8 +1,16 @@
-use crate::shader::ShaderError;
+use crate::{
+    ecs::{Component, Ecs, EcsError, Entity, Query, QueryData, QueryFilter},
+    shader::ShaderError,
+};
 
+pub use access::SystemAccess;
+
+mod access;
 pub mod controller_system;
 pub mod physics_system;
 pub mod render_system;
+pub mod schedule;
+pub mod transform_system;
 
 #[derive(Debug)]
 pub enum SystemError {
@@ -19,6 +27,66 @@ impl From<ShaderError> for SystemError {
     }
 }
 
+/// What a system is handed for one run: the world, and the change tick the system last ran at.
+#[derive(Clone, Copy)]
+pub struct SystemContext<'w> {
+    pub ecs: &'w Ecs,
+    /// Change tick of the system's previous run, 0 before its first one.
+    pub last_run: u32,
+}
+
+impl<'w> SystemContext<'w> {
+    pub fn new(ecs: &'w Ecs, last_run: u32) -> Self {
+        Self { ecs, last_run }
+    }
+
+    /// [`Ecs::query_filtered`] with `Added` and `Changed` reporting what changed since the
+    /// system last ran.
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(
+        &self,
+    ) -> Result<Query<'w, Q, F>, EcsError> {
+        self.ecs.query_filtered_since(self.last_run)
+    }
+
+    /// Entities that lost their `ComponentType` since the system last ran.
+    pub fn removed_components<ComponentType: Component>(&self) -> Vec<Entity> {
+        self.ecs
+            .removed_components_since::<ComponentType>(self.last_run)
+    }
+}
+
+/// Logic run by the [`schedule::Schedule`] against the world it is given. Components and
+/// resources are locked individually, so systems running in parallel share the world; entities
+/// and components are added or removed through [`Ecs::commands`].
 pub trait System {
+    fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError>;
+
+    /// What this system touches, used by the schedule to decide which systems may run in
+    /// parallel. Systems that don't declare anything are assumed to touch everything.
+    fn access(&self) -> SystemAccess {
+        SystemAccess::exclusive()
+    }
+}
+
+/// The previous system interface, where every system kept its own `&Mutex<Ecs>` handle to the
+/// world and locked it in `update`. Closures of the same shape implement it too.
+pub trait LegacySystem {
     fn update(&mut self) -> Result<(), SystemError>;
 }
+
+impl<F: FnMut() -> Result<(), SystemError>> LegacySystem for F {
+    fn update(&mut self) -> Result<(), SystemError> {
+        self()
+    }
+}
+
+/// Schedules a [`LegacySystem`] unchanged. It keeps working on the world behind its own handle
+/// rather than the one the schedule runs on, and is never run next to other systems. Porting
+/// it means implementing [`System`] and reading the world from the [`SystemContext`] instead.
+pub struct Legacy<S>(pub S);
+
+impl<S: LegacySystem> System for Legacy<S> {
+    fn run(&mut self, _context: SystemContext<'_>) -> Result<(), SystemError> {
+        self.0.update()
+    }
+}
@@ -1,90 +1,574 @@
-use super::{System, SystemError};
+use super::{System, SystemAccess, SystemContext, SystemError};
 use crate::{
     collider::Collider,
-    components::{gravity::GravityComponent, rigid_body::RigidBody, transform::Transform},
-    constants::GROUND_DRAG,
-    ecs::Ecs,
+    components::{
+        collider_shape::ColliderShape, collision_layers::CollisionLayers,
+        global_transform::GlobalTransform, gravity::GravityComponent, rigid_body::RigidBody,
+        static_collider::StaticCollider, transform::Transform,
+    },
+    constants::{
+        BODY_SOLVER_ITERATIONS, CONTACT_ITERATIONS, GROUND_DRAG, GROUND_NORMAL_Y, PENETRATION_SLOP,
+        POSITION_CORRECTION, RESTING_SPEED,
+    },
+    ecs::{Ecs, Entity, Events, Parent},
+    events::LandedEvent,
+    narrow_phase::PlacedShape,
     ray::Ray,
-    utils::{flatten_vector, heighten_vector, lengthen_vector},
+    utils::{create_transform_matrix, flatten_vector, heighten_vector, lengthen_vector},
 };
-use nalgebra_glm::Vec3;
-use std::sync::Mutex;
+use nalgebra_glm::{Mat4, Vec3};
+use std::collections::{HashMap, HashSet};
 
-pub struct PhysicsSystem<'a> {
-    ecs: &'a Mutex<Ecs>,
-    collider: &'a mut Collider,
+pub struct PhysicsSystem;
+
+/// The space a body's `Transform` and velocity are in, that of its parent. Collision happens
+/// in world space.
+#[derive(Clone, Copy)]
+struct Space {
+    to_world: Mat4,
+    to_local: Mat4,
+}
+
+impl Space {
+    fn of(ecs: &Ecs, entity: Entity) -> Self {
+        let to_world =
+            GlobalTransform::parent_matrix(ecs, entity).expect("Could not get parent matrix");
+
+        Self {
+            to_world,
+            to_local: to_world.try_inverse().unwrap_or_else(Mat4::identity),
+        }
+    }
+
+    fn world_point(&self, point: Vec3) -> Vec3 {
+        self.to_world.transform_point(&point.into()).coords
+    }
+
+    fn world_vector(&self, vector: Vec3) -> Vec3 {
+        self.to_world.transform_vector(&vector)
+    }
+
+    fn local_vector(&self, vector: Vec3) -> Vec3 {
+        self.to_local.transform_vector(&vector)
+    }
+
+    fn placed(&self, shape: &ColliderShape, transform: &Transform) -> PlacedShape {
+        shape.placed(&(self.to_world * create_transform_matrix(transform)))
+    }
+}
+
+/// Where a body ends up after being pushed out of the static collidables it overlaps.
+struct Resolved {
+    position: Vec3,
+    velocity: Vec3,
+    grounded: bool,
+    /// Normals of the contacts it was pushed out of, pointing into the collidables.
+    normals: Vec<Vec3>,
+}
+
+/// Pushes `shape`, placed at `position` with the rest of `transform`, out of the collidables
+/// it overlaps, deepest first. Velocity into a collidable is removed.
+fn push_out(
+    collider: &Collider,
+    shape: &ColliderShape,
+    space: &Space,
+    transform: &Transform,
+    mut position: Vec3,
+    mut velocity: Vec3,
+) -> Resolved {
+    let mut grounded = false;
+    let mut normals = Vec::new();
+
+    for _ in 0..CONTACT_ITERATIONS {
+        let moved = Transform::new(position, transform.rotation(), transform.scale());
+        let placed = space.placed(shape, &moved);
+        let Some((_, contact)) = collider
+            .contacts(&placed)
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.depth.total_cmp(&b.depth))
+        else {
+            break;
+        };
+
+        position -= space.local_vector(contact.normal * contact.depth);
+        let normal = space.local_vector(contact.normal).normalize();
+        let into = velocity.dot(&normal);
+        if into > 0.0 {
+            velocity -= normal * into;
+        }
+        // Normals point from the body into the collidable, so ground points down.
+        grounded |= -contact.normal.y >= GROUND_NORMAL_Y;
+        normals.push(contact.normal);
+    }
+
+    Resolved {
+        position,
+        velocity,
+        grounded,
+        normals,
+    }
+}
+
+/// A body taking part in contacts between bodies, or a static collidable it rests on, which
+/// has no entity and is never moved. Velocities and offsets are in world space.
+struct SolverBody {
+    entity: Option<Entity>,
+    space: Space,
+    /// How far the body is pushed to get it out of the others.
+    offset: Vec3,
+    velocity: Vec3,
+    inverse_mass: f32,
+    restitution: f32,
+    friction: f32,
+}
+
+impl SolverBody {
+    fn new(entity: Entity, rigid_body: &RigidBody, space: Space) -> Self {
+        Self {
+            entity: Some(entity),
+            space,
+            offset: Vec3::zeros(),
+            velocity: space.world_vector(rigid_body.velocity()),
+            inverse_mass: rigid_body.inverse_mass(),
+            restitution: rigid_body.restitution(),
+            friction: rigid_body.friction(),
+        }
+    }
+
+    /// Something static `body` rests on, made of the same material.
+    fn immovable(body: &SolverBody) -> Self {
+        Self {
+            entity: None,
+            velocity: Vec3::zeros(),
+            inverse_mass: 0.0,
+            ..*body
+        }
+    }
+}
+
+/// Two solver bodies touching, with the normal pointing from the first into the second.
+struct SolverContact {
+    first: usize,
+    second: usize,
+    normal: Vec3,
+    depth: f32,
+    /// Speed along the normal the two should part at.
+    bounce: f32,
 }
 
-impl<'a> PhysicsSystem<'a> {
-    pub fn init(ecs: &'a Mutex<Ecs>, collider: &'a mut Collider) -> Self {
-        Self { ecs, collider }
+/// The impulse to give the second body of the contact, and the opposite to the first, so they
+/// stop moving into each other and slide against their friction.
+fn impulse(first: &SolverBody, second: &SolverBody, contact: &SolverContact) -> Vec3 {
+    let inverse_masses = first.inverse_mass + second.inverse_mass;
+    let relative = second.velocity - first.velocity;
+    let parting = relative.dot(&contact.normal);
+    if inverse_masses <= 0.0 || parting >= contact.bounce {
+        return Vec3::zeros();
     }
+
+    let push = (contact.bounce - parting) / inverse_masses;
+    let sliding = relative - contact.normal * parting;
+    let speed = sliding.norm();
+    if speed <= f32::EPSILON {
+        return contact.normal * push;
+    }
+
+    // Friction stops the sliding at most, and grips at most as hard as the bodies are pushed.
+    let friction = (first.friction * second.friction).sqrt();
+    let grip = (speed / inverse_masses).min(friction * push);
+    contact.normal * push - sliding / speed * grip
 }
 
-impl<'a> System for PhysicsSystem<'a> {
-    fn update(&mut self) -> Result<(), SystemError> {
-        let ecs = self.ecs.lock().map_err(|_| SystemError::LockError)?;
-
-        let mut rigid_bodies = ecs
-            .get_component_vec::<RigidBody>()
-            .expect("Could not get component vector");
-        let mut transforms = ecs
-            .get_component_vec::<Transform>()
-            .expect("Could not get component vector");
-        let mut gravities = ecs
-            .get_component_vec::<GravityComponent>()
-            .expect("Could not get component vector");
-
-        let union = rigid_bodies
-            .iter_mut()
-            .zip(transforms.iter_mut().zip(gravities.iter_mut()))
-            .filter_map(|(rigid_body, (transform, gravity))| {
-                Some((rigid_body.as_mut()?, transform.as_mut()?, gravity.as_mut()?))
+/// Resolves contacts between bodies, and between them and the static collidables they were
+/// pushed off in `supports`. Velocities are fixed with impulses first, then overlaps are
+/// partly pushed apart. Returns the bodies that landed on another body.
+fn resolve_bodies(
+    ecs: &Ecs,
+    collider: &mut Collider,
+    supports: &HashMap<Entity, Vec<Vec3>>,
+) -> Vec<Entity> {
+    let contacts = collider.body_contacts();
+    if contacts.is_empty() {
+        return Vec::new();
+    }
+
+    let mut query = ecs
+        .query::<(
+            &mut RigidBody,
+            &mut Transform,
+            &ColliderShape,
+            Option<&CollisionLayers>,
+        )>()
+        .expect("Could not query colliding bodies");
+    let mut bodies: Vec<SolverBody> = Vec::new();
+    let mut indices: HashMap<Entity, usize> = HashMap::new();
+    let mut solver_contacts = Vec::new();
+
+    for (first, second, contact) in contacts {
+        let [first, second] = [first, second].map(|entity| {
+            *indices.entry(entity).or_insert_with(|| {
+                let (rigid_body, _, _, _) =
+                    query.get(entity).expect("Could not get colliding body");
+                bodies.push(SolverBody::new(entity, &rigid_body, Space::of(ecs, entity)));
+                bodies.len() - 1
+            })
+        });
+        solver_contacts.push(SolverContact {
+            first,
+            second,
+            normal: contact.normal,
+            depth: contact.depth,
+            bounce: 0.0,
+        });
+    }
+
+    // Bodies were already pushed out of the static collidables, so those are only kept from
+    // moving the bodies back into them.
+    for index in 0..bodies.len() {
+        let entity = bodies[index].entity.expect("Only bodies were added so far");
+        for normal in supports.get(&entity).into_iter().flatten() {
+            bodies.push(SolverBody::immovable(&bodies[index]));
+            solver_contacts.push(SolverContact {
+                first: index,
+                second: bodies.len() - 1,
+                normal: *normal,
+                depth: 0.0,
+                bounce: 0.0,
             });
+        }
+    }
+
+    let mut landed = Vec::new();
+    for contact in solver_contacts.iter_mut() {
+        let (first, second) = (&bodies[contact.first], &bodies[contact.second]);
+        let parting = (second.velocity - first.velocity).dot(&contact.normal);
+        if parting >= -RESTING_SPEED {
+            continue;
+        }
+
+        contact.bounce = -parting * first.restitution.max(second.restitution);
+        // Normals point from the first body into the second, so the lower one is the ground.
+        if -contact.normal.y >= GROUND_NORMAL_Y {
+            landed.extend(first.entity);
+        } else if contact.normal.y >= GROUND_NORMAL_Y {
+            landed.extend(second.entity);
+        }
+    }
+
+    for _ in 0..BODY_SOLVER_ITERATIONS {
+        for contact in solver_contacts.iter() {
+            let impulse = impulse(&bodies[contact.first], &bodies[contact.second], contact);
+            let first = &mut bodies[contact.first];
+            first.velocity -= impulse * first.inverse_mass;
+            let second = &mut bodies[contact.second];
+            second.velocity += impulse * second.inverse_mass;
+        }
+    }
 
-        for (rigid_body, transform, gravity) in union {
+    for contact in solver_contacts.iter() {
+        let inverse_masses =
+            bodies[contact.first].inverse_mass + bodies[contact.second].inverse_mass;
+        if inverse_masses <= 0.0 {
+            continue;
+        }
+
+        let overlap = (contact.depth - PENETRATION_SLOP).max(0.0);
+        let correction = contact.normal * overlap * POSITION_CORRECTION / inverse_masses;
+        let first = &mut bodies[contact.first];
+        first.offset -= correction * first.inverse_mass;
+        let second = &mut bodies[contact.second];
+        second.offset += correction * second.inverse_mass;
+    }
+
+    for body in bodies.iter() {
+        let Some(entity) = body.entity else {
+            continue;
+        };
+        let (mut rigid_body, mut transform, shape, layers) =
+            query.get(entity).expect("Could not get colliding body");
+
+        // Pushing bodies apart can push one into a static collidable again.
+        let space = body.space;
+        let mut velocity = space.local_vector(body.velocity);
+        if body.offset != Vec3::zeros() {
+            let position = transform.position() + space.local_vector(body.offset);
+            let resolved = push_out(collider, shape, &space, &transform, position, velocity);
+            velocity = resolved.velocity;
+            transform.translate(resolved.position);
+        }
+        rigid_body.set_velocity(velocity);
+
+        let layers = layers.copied().unwrap_or_default();
+        let matrix = space.to_world * create_transform_matrix(&transform);
+        collider.update_body(entity, shape, layers, &matrix);
+    }
+
+    landed
+}
+
+impl PhysicsSystem {
+    pub fn init() -> Self {
+        Self
+    }
+}
+
+impl System for PhysicsSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .write::<RigidBody>()
+            .write::<Transform>()
+            .read::<Parent>()
+            .read::<GlobalTransform>()
+            .read::<GravityComponent>()
+            .read::<ColliderShape>()
+            .read::<CollisionLayers>()
+            .read::<StaticCollider>()
+            .write::<Collider>()
+            .write::<Events<LandedEvent>>()
+    }
+
+    fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+        let ecs = context.ecs;
+        let mut collider = ecs
+            .resource_mut::<Collider>()
+            .expect("Could not get collider");
+        let mut landed = ecs
+            .event_writer::<LandedEvent>()
+            .expect("Could not get landed events");
+        StaticCollider::sync_moved(ecs, context.last_run, &mut collider)
+            .expect("Could not sync static colliders");
+
+        let mut query = ecs
+            .query::<(
+                &mut RigidBody,
+                &mut Transform,
+                Option<&GravityComponent>,
+                Option<&ColliderShape>,
+                Option<&CollisionLayers>,
+            )>()
+            .expect("Could not query rigid bodies");
+        let mut bodies = HashSet::new();
+        let mut supports = HashMap::new();
+
+        for (entity, (mut rigid_body, mut transform, gravity, shape, layers)) in query.iter() {
+            let space = Space::of(ecs, entity);
+            let gravity = gravity.map_or(Vec3::zeros(), |gravity| gravity.force);
             let mut new_position = transform.position() + rigid_body.velocity();
-            let mut new_velocity = rigid_body.velocity() + rigid_body.net_force() + gravity.force;
+            let mut new_velocity = rigid_body.velocity() + rigid_body.net_force() + gravity;
 
             let up_ray = Ray::new(
-                heighten_vector(new_position, rigid_body.height() / 2.0),
+                space.world_point(heighten_vector(new_position, rigid_body.height() / 2.0)),
                 Vec3::new(0.0, 1.0, 0.0),
             );
             let down_ray = Ray::new(
-                heighten_vector(new_position, -rigid_body.height() / 2.0),
+                space.world_point(heighten_vector(new_position, -rigid_body.height() / 2.0)),
                 Vec3::new(0.0, -1.0, 0.0),
             );
             let north_ray = Ray::new(
-                lengthen_vector(new_position, rigid_body.radius() / 2.0),
+                space.world_point(lengthen_vector(new_position, rigid_body.radius() / 2.0)),
                 Vec3::new(1.0, 0.0, 0.0),
             );
             let south_ray = Ray::new(
-                lengthen_vector(new_position, rigid_body.radius() / 2.0),
+                space.world_point(lengthen_vector(new_position, rigid_body.radius() / 2.0)),
                 Vec3::new(-1.0, 0.0, 0.0),
             );
-            let east_ray = Ray::new(new_position, Vec3::new(0.0, 0.0, 1.0));
-            let west_ray = Ray::new(new_position, Vec3::new(0.0, 0.0, -1.0));
+            let east_ray = Ray::new(space.world_point(new_position), Vec3::new(0.0, 0.0, 1.0));
+            let west_ray = Ray::new(space.world_point(new_position), Vec3::new(0.0, 0.0, -1.0));
 
-            if self.collider.collides(&north_ray) || self.collider.collides(&south_ray) {
+            if collider.collides(&north_ray) || collider.collides(&south_ray) {
                 new_position = Vec3::new(transform.position().x, new_position.y, new_position.z);
                 new_velocity = Vec3::new(0.0, new_velocity.y, new_velocity.z);
             }
 
-            if self.collider.collides(&up_ray) || self.collider.collides(&down_ray) {
+            let grounded = collider.collides(&down_ray);
+            if grounded && rigid_body.velocity().y < 0.0 {
+                landed.send(LandedEvent { entity });
+            }
+
+            if collider.collides(&up_ray) || grounded {
                 new_position = Vec3::new(new_position.x, transform.position().y, new_position.z);
                 new_velocity = GROUND_DRAG * flatten_vector(new_velocity);
             }
 
-            if self.collider.collides(&east_ray) || self.collider.collides(&west_ray) {
+            if collider.collides(&east_ray) || collider.collides(&west_ray) {
                 new_position = Vec3::new(new_position.x, new_position.y, transform.position().z);
                 new_velocity = Vec3::new(new_velocity.x, new_velocity.y, 0.0);
             }
 
-            transform.translate(new_position);
+            // Rays only look around a few points, bodies with a shape are also kept out of solids.
+            if let Some(shape) = shape {
+                let resolved = push_out(
+                    &collider,
+                    shape,
+                    &space,
+                    &transform,
+                    new_position,
+                    new_velocity,
+                );
+                new_position = resolved.position;
+                new_velocity = resolved.velocity;
+
+                let mut normals = resolved.normals;
+                if grounded {
+                    normals.push(Vec3::new(0.0, -1.0, 0.0));
+                }
+                supports.insert(entity, normals);
+
+                if resolved.grounded && !grounded {
+                    if rigid_body.velocity().y < 0.0 {
+                        landed.send(LandedEvent { entity });
+                    }
+                    new_velocity = GROUND_DRAG * flatten_vector(new_velocity);
+                }
+            }
+
+            // Resting bodies keep their transform untouched so it isn't flagged as changed.
+            if new_position != transform.position() {
+                transform.translate(new_position);
+            }
             rigid_body.set_velocity(new_velocity);
             rigid_body.reset_force();
+
+            if let Some(shape) = shape {
+                let layers = layers.copied().unwrap_or_default();
+                let matrix = space.to_world * create_transform_matrix(&transform);
+                collider.update_body(entity, shape, layers, &matrix);
+                bodies.insert(entity);
+            }
+        }
+        collider.retain_bodies(|entity| bodies.contains(&entity));
+        drop(query);
+
+        for entity in resolve_bodies(ecs, &mut collider, &supports) {
+            landed.send(LandedEvent { entity });
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// World with a wide static floor whose top is at `y = 0`.
+    fn world() -> Ecs {
+        let mut ecs = Ecs::new();
+        ecs.set_auto_register(true);
+        ecs.register_component::<ColliderShape>()
+            .expect("Could not register component");
+        ecs.register_component::<CollisionLayers>()
+            .expect("Could not register component");
+        ecs.register_component::<GravityComponent>()
+            .expect("Could not register component");
+        ecs.insert_resource(Collider::new());
+        ecs.insert_resource(Events::<LandedEvent>::new());
+        StaticCollider::register_hooks(&mut ecs);
+
+        let floor = Transform::new(
+            Vec3::new(0.0, -0.5, 0.0),
+            None,
+            Some(Vec3::new(40.0, 1.0, 40.0)),
+        );
+        ecs.spawn((StaticCollider, ColliderShape::cube(), floor))
+            .expect("Could not spawn floor");
+        ecs.apply_commands().expect("Could not apply commands");
+
+        ecs
+    }
+
+    fn spawn_box(ecs: &mut Ecs, position: Vec3, rigid_body: RigidBody) -> Entity {
+        let transform = Transform::new(position, None, None);
+        let entity = ecs
+            .spawn((transform, rigid_body, ColliderShape::cube()))
+            .expect("Could not spawn box");
+        ecs.apply_commands().expect("Could not apply commands");
+
+        entity
+    }
+
+    fn position(ecs: &Ecs, entity: Entity) -> Vec3 {
+        ecs.get_component_vec::<Transform>()
+            .expect("Could not get transforms")
+            .get(entity.index())
+            .expect("Could not find transform")
+            .position()
+    }
+
+    fn velocity(ecs: &Ecs, entity: Entity) -> Vec3 {
+        ecs.get_component_vec::<RigidBody>()
+            .expect("Could not get rigid bodies")
+            .get(entity.index())
+            .expect("Could not find rigid body")
+            .velocity()
+    }
+
+    fn tick(ecs: &mut Ecs, physics: &mut PhysicsSystem) {
+        let context = SystemContext::new(ecs, ecs.last_change_tick());
+        physics.run(context).expect("Could not run physics");
+        ecs.update_events();
+        ecs.clear_trackers();
+    }
+
+    #[test]
+    fn stacked_boxes_stay_put() {
+        for height in [2, 3] {
+            let mut ecs = world();
+            let boxes: Vec<Entity> = (0..height)
+                .map(|level| {
+                    let position = Vec3::new(0.0, 0.5 + level as f32, 0.0);
+                    let entity = spawn_box(&mut ecs, position, RigidBody::default());
+                    ecs.add_component(entity, GravityComponent::default())
+                        .expect("Could not add gravity");
+                    entity
+                })
+                .collect();
+
+            let mut physics = PhysicsSystem::init();
+            for _ in 0..600 {
+                tick(&mut ecs, &mut physics);
+
+                for (level, entity) in boxes.iter().enumerate() {
+                    let position = position(&ecs, *entity);
+                    assert!(
+                        (position.y - (0.5 + level as f32)).abs() < 0.05,
+                        "Box {} of {} is at {:?}",
+                        level,
+                        height,
+                        position
+                    );
+                    assert!(position.x.abs() < 0.01 && position.z.abs() < 0.01);
+                }
+            }
+
+            for entity in boxes {
+                assert!(velocity(&ecs, entity).norm() < 0.002);
+            }
+        }
+    }
+
+    #[test]
+    fn moving_bodies_do_not_pass_through_each_other() {
+        let mut ecs = world();
+        // High above the floor and without gravity, so only the bodies meet.
+        let resting = spawn_box(&mut ecs, Vec3::new(0.0, 5.0, 0.0), RigidBody::default());
+        let moving = spawn_box(&mut ecs, Vec3::new(-3.0, 5.0, 0.0), RigidBody::default());
+        ecs.get_component::<RigidBody>(moving)
+            .expect("Could not get rigid body")
+            .expect("Could not find rigid body")
+            .set_velocity(Vec3::new(0.05, 0.0, 0.0));
+
+        let mut physics = PhysicsSystem::init();
+        for _ in 0..200 {
+            tick(&mut ecs, &mut physics);
+
+            let gap = position(&ecs, resting).x - position(&ecs, moving).x;
+            assert!(gap > 0.95, "Boxes overlap, {} apart", gap);
+        }
+
+        // Both share the momentum after an inelastic hit.
+        assert!(position(&ecs, resting).x > 1.0);
+        assert!((velocity(&ecs, resting).x - 0.025).abs() < 0.003);
+        assert!((velocity(&ecs, moving).x - 0.025).abs() < 0.003);
+    }
+}
@@ -1,91 +1,68 @@
-use super::{System, SystemError};
+use super::{System, SystemAccess, SystemContext, SystemError};
 use crate::{
     camera::Camera,
     components::{
-        camera_followable::CameraFollowable, controllable::Controllable, mesh::MeshComponent,
-        transform::Transform,
+        camera_followable::CameraFollowable, controllable::Controllable,
+        global_transform::GlobalTransform, mesh::MeshComponent,
     },
-    constants::CAMERA_FOV,
-    ecs::Ecs,
     mesh_manager::MeshManager,
     shader::Shader,
-    utils::create_transform_matrix,
+    time::Time,
 };
-use std::sync::Mutex;
 
 pub struct RenderSystem<'a> {
-    ecs: &'a Mutex<Ecs>,
-    mesh_manager: &'a Mutex<MeshManager>,
     shader: &'a Shader,
 }
 
 impl<'a> RenderSystem<'a> {
-    pub fn init(
-        ecs: &'a Mutex<Ecs>,
-        mesh_manager: &'a Mutex<MeshManager>,
-        shader: &'a Shader,
-    ) -> Self {
-        Self {
-            ecs,
-            mesh_manager,
-            shader,
-        }
+    pub fn init(shader: &'a Shader) -> Self {
+        Self { shader }
     }
 }
 
 impl<'a> System for RenderSystem<'a> {
-    fn update(&mut self) -> Result<(), SystemError> {
-        let ecs = self.ecs.lock().map_err(|_| SystemError::LockError)?;
-        let mesh_manager = self
-            .mesh_manager
-            .lock()
-            .map_err(|_| SystemError::LockError)?;
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<Controllable>()
+            .read::<CameraFollowable>()
+            .read::<GlobalTransform>()
+            .read::<MeshComponent>()
+            .read::<MeshManager>()
+            .read::<Camera>()
+            .read::<Time>()
+    }
+
+    fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+        let ecs = context.ecs;
+        let mesh_manager = ecs
+            .resource::<MeshManager>()
+            .expect("Could not get mesh manager");
+        let camera = ecs.resource::<Camera>().expect("Could not get camera");
+        // Rendering lags up to one tick behind the simulation, blending towards its latest state.
+        let alpha = ecs.resource::<Time>().expect("Could not get time").alpha();
 
-        let controlled = ecs
-            .get_component_vec::<Controllable>()
-            .expect("Could not get controlled component");
-        let camera_followable = ecs
-            .get_component_vec::<CameraFollowable>()
-            .expect("Could not get camera followable component");
-        let transforms = ecs
-            .get_component_vec::<Transform>()
-            .expect("Could not get transform component");
+        let mut cameras = ecs
+            .query::<(&Controllable, &CameraFollowable, &GlobalTransform)>()
+            .expect("Could not query camera follow");
 
-        let (camera_control, camera_follow, camera_transform) = controlled
+        let (_, (camera_control, camera_follow, camera_transform)) = cameras
             .iter()
-            .zip(camera_followable.iter().zip(transforms.iter()))
-            .find_map(|(controlled, (camera_followable, transform))| {
-                if let Some(camera_followable) = camera_followable {
-                    if camera_followable.followed() {
-                        return Some((
-                            controlled.as_ref()?,
-                            camera_followable,
-                            transform.as_ref()?,
-                        ));
-                    }
-                }
-                None
-            })
+            .find(|(_, (_, camera_followable, _))| camera_followable.followed())
             .expect("Missing camera follow");
 
-        let camera_position =
-            camera_transform.position() + camera_follow.camera_relative_position();
+        let camera_position = camera_transform.interpolated_position(alpha)
+            + camera_follow.camera_relative_position();
         let view_transform = Camera::view_transform(&camera_position, &camera_control.facing());
-        let projection_transform = Camera::projection_transform(CAMERA_FOV);
+        let projection_transform = Camera::projection_transform(camera.fov());
 
-        let meshes = ecs
-            .get_component_vec::<MeshComponent>()
-            .expect("Could not get component vector");
-
-        let union = transforms
-            .iter()
-            .zip(meshes.iter())
-            .filter_map(|(transform, mesh)| Some((transform.as_ref()?, mesh.as_ref()?)));
+        let mut meshes = ecs
+            .query::<(&GlobalTransform, &MeshComponent)>()
+            .expect("Could not query meshes");
 
-        for (transform, mesh) in union {
+        for (_, (transform, mesh)) in meshes.iter() {
             let MeshComponent { id } = mesh;
 
-            let model_transform = create_transform_matrix(transform);
+            let model_transform = transform.interpolated(alpha);
 
             let mesh = mesh_manager.get_mesh(*id).expect("Missing mesh");
             mesh.draw_instance(
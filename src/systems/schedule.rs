@@ -0,0 +1,861 @@
+use super::{System, SystemAccess, SystemContext, SystemError};
+use crate::ecs::{clamp_tick, Ecs, EcsError};
+use std::thread::{self, ThreadId};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Input,
+    FixedUpdate,
+    PostPhysics,
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::Input,
+        Stage::FixedUpdate,
+        Stage::PostPhysics,
+        Stage::Render,
+    ];
+}
+
+#[derive(Debug)]
+pub enum ScheduleError {
+    System {
+        stage: Stage,
+        system: &'static str,
+        error: SystemError,
+    },
+    Commands {
+        stage: Stage,
+        system: &'static str,
+        error: EcsError,
+    },
+    UnknownSystem {
+        stage: Stage,
+        system: &'static str,
+        dependency: &'static str,
+    },
+    Cycle {
+        stage: Stage,
+        systems: Vec<&'static str>,
+    },
+}
+
+impl ScheduleError {
+    pub fn requested_quit(&self) -> bool {
+        matches!(
+            self,
+            ScheduleError::System {
+                error: SystemError::RequestedQuit,
+                ..
+            }
+        )
+    }
+}
+
+type RunCondition = Box<dyn Fn(&Ecs) -> bool>;
+
+enum SystemKind<'a> {
+    /// Runs on the thread driving the schedule, e.g. because it owns window or GL state.
+    Local(Box<dyn System + 'a>),
+    /// May run on a worker thread next to other systems whose access does not conflict.
+    Parallel(Box<dyn System + Send + 'a>),
+}
+
+impl<'a> SystemKind<'a> {
+    fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+        match self {
+            SystemKind::Local(system) => system.run(context),
+            SystemKind::Parallel(system) => system.run(context),
+        }
+    }
+}
+
+pub struct ScheduledSystem<'a> {
+    name: &'static str,
+    stage: Stage,
+    system: SystemKind<'a>,
+    access: SystemAccess,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+    run_conditions: Vec<RunCondition>,
+    /// Change tick of the system's latest run, what its `Added` and `Changed` filters compare
+    /// against.
+    last_run: u32,
+}
+
+impl<'a> ScheduledSystem<'a> {
+    /// Runs this system ahead of `system` when both are in the same stage.
+    pub fn before(&mut self, system: &'static str) -> &mut Self {
+        self.before.push(system);
+        self
+    }
+
+    /// Runs this system after `system` when both are in the same stage.
+    pub fn after(&mut self, system: &'static str) -> &mut Self {
+        self.after.push(system);
+        self
+    }
+
+    /// Skips this system for any run where `condition` returns false.
+    pub fn run_if(&mut self, condition: impl Fn(&Ecs) -> bool + 'static) -> &mut Self {
+        self.run_conditions.push(Box::new(condition));
+        self
+    }
+}
+
+/// Runs systems stage by stage, in an order satisfying their `before`/`after` constraints.
+/// Consecutive parallel systems with non-conflicting access run together on worker threads, and
+/// deferred commands are applied after every batch in the order the systems were scheduled, so
+/// the outcome is the same as running everything one after another, except that entities spawned
+/// by systems sharing a batch are numbered in the order the systems get to them. The world is
+/// only borrowed for the duration of each run. Every batch and its commands get their own change
+/// tick, and every system is handed the tick it last ran at, so its `Added` and `Changed` filters
+/// and removed components report everything changed since then.
+pub struct Schedule<'a> {
+    systems: Vec<ScheduledSystem<'a>>,
+}
+
+impl<'a> Default for Schedule<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Schedule<'a> {
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+        }
+    }
+
+    fn push(
+        &mut self,
+        stage: Stage,
+        name: &'static str,
+        system: SystemKind<'a>,
+        access: SystemAccess,
+    ) -> &mut ScheduledSystem<'a> {
+        self.systems.push(ScheduledSystem {
+            name,
+            stage,
+            system,
+            access,
+            before: Vec::new(),
+            after: Vec::new(),
+            run_conditions: Vec::new(),
+            last_run: 0,
+        });
+
+        self.systems.last_mut().expect("System was just added")
+    }
+
+    /// Adds a system that always runs on the calling thread, on its own.
+    pub fn add_system(
+        &mut self,
+        stage: Stage,
+        name: &'static str,
+        system: impl System + 'a,
+    ) -> &mut ScheduledSystem<'a> {
+        self.push(
+            stage,
+            name,
+            SystemKind::Local(Box::new(system)),
+            SystemAccess::exclusive(),
+        )
+    }
+
+    /// Adds a system that may run on a worker thread alongside other parallel systems, as
+    /// allowed by its declared [`System::access`].
+    pub fn add_parallel_system(
+        &mut self,
+        stage: Stage,
+        name: &'static str,
+        system: impl System + Send + 'a,
+    ) -> &mut ScheduledSystem<'a> {
+        let access = system.access();
+
+        self.push(stage, name, SystemKind::Parallel(Box::new(system)), access)
+    }
+
+    /// Orders the systems of `stage` with Kahn's algorithm, keeping insertion order between
+    /// systems that are not constrained against each other.
+    fn stage_order(&self, stage: Stage) -> Result<Vec<usize>, ScheduleError> {
+        let members: Vec<usize> = (0..self.systems.len())
+            .filter(|&i| self.systems[i].stage == stage)
+            .collect();
+        let position = |name: &'static str, system: &'static str| {
+            members
+                .iter()
+                .position(|&i| self.systems[i].name == name)
+                .ok_or(ScheduleError::UnknownSystem {
+                    stage,
+                    system,
+                    dependency: name,
+                })
+        };
+
+        // edges[a] holds every member that has to wait for member `a`.
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); members.len()];
+        let mut waiting_on = vec![0; members.len()];
+        for (member, &i) in members.iter().enumerate() {
+            let system = &self.systems[i];
+            for &before in system.before.iter() {
+                let other = position(before, system.name)?;
+                edges[member].push(other);
+                waiting_on[other] += 1;
+            }
+            for &after in system.after.iter() {
+                let other = position(after, system.name)?;
+                edges[other].push(member);
+                waiting_on[member] += 1;
+            }
+        }
+
+        let mut order = Vec::with_capacity(members.len());
+        let mut done = vec![false; members.len()];
+        while order.len() < members.len() {
+            let next = (0..members.len()).find(|&member| !done[member] && waiting_on[member] == 0);
+            let Some(next) = next else {
+                let systems = (0..members.len())
+                    .filter(|&member| !done[member])
+                    .map(|member| self.systems[members[member]].name)
+                    .collect();
+
+                return Err(ScheduleError::Cycle { stage, systems });
+            };
+
+            done[next] = true;
+            for &other in edges[next].iter() {
+                waiting_on[other] -= 1;
+            }
+            order.push(members[next]);
+        }
+
+        Ok(order)
+    }
+
+    /// Splits an ordered stage into batches of systems that can run at the same time. A system
+    /// joins the current batch only if it is parallel, does not conflict with any member and is
+    /// not ordered after one of them. Batches never reorder systems.
+    fn batches(&self, order: Vec<usize>) -> Vec<Vec<usize>> {
+        let mut batches: Vec<Vec<usize>> = Vec::new();
+
+        for i in order {
+            let system = &self.systems[i];
+            let joins = batches.last().is_some_and(|batch| {
+                batch.iter().all(|&j| {
+                    let other = &self.systems[j];
+
+                    matches!(system.system, SystemKind::Parallel(_))
+                        && matches!(other.system, SystemKind::Parallel(_))
+                        && !system.access.conflicts_with(&other.access)
+                        && !system.after.contains(&other.name)
+                        && !other.before.contains(&system.name)
+                })
+            });
+
+            match batches.last_mut() {
+                Some(batch) if joins => batch.push(i),
+                _ => batches.push(vec![i]),
+            }
+        }
+
+        batches
+    }
+
+    fn system_error(&self, stage: Stage, i: usize, error: SystemError) -> ScheduleError {
+        ScheduleError::System {
+            stage,
+            system: self.systems[i].name,
+            error,
+        }
+    }
+
+    pub fn run_stage(&mut self, stage: Stage, ecs: &mut Ecs) -> Result<(), ScheduleError> {
+        let order = self.stage_order(stage)?;
+
+        for batch in self.batches(order) {
+            let batch: Vec<usize> = batch
+                .into_iter()
+                .filter(|&i| {
+                    self.systems[i]
+                        .run_conditions
+                        .iter()
+                        .all(|condition| condition(ecs))
+                })
+                .collect();
+
+            if batch.is_empty() {
+                continue;
+            }
+
+            let tick = ecs.advance_change_tick();
+            for &i in batch.iter() {
+                clamp_tick(&mut self.systems[i].last_run, tick);
+            }
+            let threads = match batch.as_slice() {
+                [i] => {
+                    let i = *i;
+                    let system = &mut self.systems[i];
+                    let context = SystemContext::new(ecs, system.last_run);
+                    system
+                        .system
+                        .run(context)
+                        .map_err(|error| self.system_error(stage, i, error))?;
+
+                    vec![thread::current().id()]
+                }
+                _ => self.run_parallel(stage, &batch, ecs)?,
+            };
+            for &i in batch.iter() {
+                self.systems[i].last_run = tick;
+            }
+
+            // Commands are stamped after the batch, so the systems that queued them see them.
+            ecs.advance_change_tick();
+            ecs.order_commands_by_thread(&threads);
+            ecs.apply_commands()
+                .map_err(|error| ScheduleError::Commands {
+                    stage,
+                    system: self.systems[batch[0]].name,
+                    error,
+                })?;
+        }
+
+        // Removals are kept until every system has had a run to see them.
+        let change_tick = ecs.change_tick();
+        let oldest_run = self
+            .systems
+            .iter()
+            .map(|system| system.last_run)
+            .max_by_key(|&last_run| change_tick.wrapping_sub(last_run));
+        if let Some(oldest_run) = oldest_run {
+            ecs.keep_removals_since(oldest_run);
+        }
+
+        Ok(())
+    }
+
+    /// Runs every system of `batch` on its own scoped thread and returns the threads in batch
+    /// order. Errors are reported for the first failing system in batch order.
+    fn run_parallel(
+        &mut self,
+        stage: Stage,
+        batch: &[usize],
+        ecs: &Ecs,
+    ) -> Result<Vec<ThreadId>, ScheduleError> {
+        let mut systems: Vec<(usize, u32, &mut (dyn System + Send + 'a))> = self
+            .systems
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, scheduled)| match &mut scheduled.system {
+                SystemKind::Parallel(system) if batch.contains(&i) => {
+                    Some((i, scheduled.last_run, system.as_mut()))
+                }
+                _ => None,
+            })
+            .collect();
+        systems.sort_by_key(|(i, _, _)| batch.iter().position(|j| j == i));
+
+        let results: Vec<(usize, ThreadId, Result<(), SystemError>)> = thread::scope(|scope| {
+            let handles: Vec<_> = systems
+                .into_iter()
+                .map(|(i, last_run, system)| {
+                    scope.spawn(move || {
+                        let result = system.run(SystemContext::new(ecs, last_run));
+                        (i, thread::current().id(), result)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("System thread panicked"))
+                .collect()
+        });
+
+        let mut threads = Vec::with_capacity(results.len());
+        for (i, thread, result) in results {
+            result.map_err(|error| self.system_error(stage, i, error))?;
+            threads.push(thread);
+        }
+
+        Ok(threads)
+    }
+
+    pub fn run(&mut self, ecs: &mut Ecs) -> Result<(), ScheduleError> {
+        for stage in Stage::ALL {
+            self.run_stage(stage, ecs)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::{Added, Changed},
+        systems::{Legacy, LegacySystem},
+    };
+    use std::{sync::Mutex, thread::sleep, time::Duration};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct A(u32);
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct B(u32);
+
+    /// Names of the systems whose commands were applied, in order.
+    #[derive(Default)]
+    struct Applied(Vec<&'static str>);
+
+    struct Sum(u32);
+
+    /// Does nothing but declare `access`.
+    struct Idle(SystemAccess);
+
+    impl System for Idle {
+        fn run(&mut self, _context: SystemContext<'_>) -> Result<(), SystemError> {
+            Ok(())
+        }
+
+        fn access(&self) -> SystemAccess {
+            self.0.clone()
+        }
+    }
+
+    fn writes_a() -> Idle {
+        Idle(SystemAccess::new().write::<A>())
+    }
+
+    fn reads_a() -> Idle {
+        Idle(SystemAccess::new().read::<A>())
+    }
+
+    fn writes_b() -> Idle {
+        Idle(SystemAccess::new().write::<B>())
+    }
+
+    fn batches(schedule: &Schedule, stage: Stage) -> Vec<Vec<&'static str>> {
+        let order = schedule.stage_order(stage).expect("Could not order stage");
+        schedule
+            .batches(order)
+            .into_iter()
+            .map(|batch| {
+                batch
+                    .into_iter()
+                    .map(|i| schedule.systems[i].name)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn disjoint_systems_share_a_batch() {
+        let mut schedule = Schedule::new();
+        schedule.add_parallel_system(Stage::FixedUpdate, "a", writes_a());
+        schedule.add_parallel_system(Stage::FixedUpdate, "b", writes_b());
+
+        assert_eq!(batches(&schedule, Stage::FixedUpdate), vec![vec!["a", "b"]]);
+    }
+
+    #[test]
+    fn conflicting_systems_get_their_own_batch() {
+        let mut schedule = Schedule::new();
+        schedule.add_parallel_system(Stage::FixedUpdate, "write", writes_a());
+        schedule.add_parallel_system(Stage::FixedUpdate, "read", reads_a());
+        schedule.add_parallel_system(Stage::FixedUpdate, "other", writes_b());
+        schedule.add_system(Stage::FixedUpdate, "local", writes_b());
+
+        assert_eq!(
+            batches(&schedule, Stage::FixedUpdate),
+            vec![vec!["write"], vec!["read", "other"], vec!["local"]]
+        );
+    }
+
+    #[test]
+    fn ordering_splits_would_be_batches() {
+        let mut schedule = Schedule::new();
+        schedule.add_parallel_system(Stage::FixedUpdate, "a", writes_a());
+        schedule
+            .add_parallel_system(Stage::FixedUpdate, "b", writes_b())
+            .after("a");
+        schedule.add_parallel_system(Stage::Render, "a", writes_a());
+        schedule
+            .add_parallel_system(Stage::Render, "b", writes_b())
+            .before("a");
+
+        assert_eq!(
+            batches(&schedule, Stage::FixedUpdate),
+            vec![vec!["a"], vec!["b"]]
+        );
+        assert_eq!(
+            batches(&schedule, Stage::Render),
+            vec![vec!["b"], vec!["a"]]
+        );
+    }
+
+    /// Waits `delay`, then queues a command recording its name.
+    struct Spawner {
+        name: &'static str,
+        delay: Duration,
+        access: SystemAccess,
+    }
+
+    impl System for Spawner {
+        fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+            let ecs = context.ecs;
+            sleep(self.delay);
+            let name = self.name;
+            ecs.commands().add(move |ecs| {
+                ecs.resource_mut::<Applied>()?.0.push(name);
+                Ok(())
+            });
+
+            Ok(())
+        }
+
+        fn access(&self) -> SystemAccess {
+            self.access.clone()
+        }
+    }
+
+    #[test]
+    fn parallel_commands_apply_in_schedule_order() {
+        let mut ecs = Ecs::new();
+        ecs.insert_resource(Applied::default());
+
+        // Later systems finish first, so their commands are queued first.
+        let mut schedule = Schedule::new();
+        let names = ["first", "second", "third"];
+        for (index, name) in names.into_iter().enumerate() {
+            let spawner = Spawner {
+                name,
+                delay: Duration::from_millis(30 - index as u64 * 10),
+                access: SystemAccess::new(),
+            };
+            schedule.add_parallel_system(Stage::FixedUpdate, name, spawner);
+        }
+        assert_eq!(batches(&schedule, Stage::FixedUpdate), vec![names.to_vec()]);
+
+        schedule
+            .run_stage(Stage::FixedUpdate, &mut ecs)
+            .expect("Could not run stage");
+
+        let applied = ecs.resource::<Applied>().expect("Could not get applied");
+        assert_eq!(applied.0, names);
+    }
+
+    /// Doubles every `A` and spawns another.
+    struct DoubleA;
+
+    impl System for DoubleA {
+        fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+            let ecs = context.ecs;
+            let mut query = ecs.query::<&mut A>().expect("Could not query A");
+            for (_, mut a) in query.iter() {
+                a.0 *= 2;
+            }
+            ecs.commands()
+                .spawn()
+                .map_err(|_| SystemError::EntityError)?
+                .insert(A(1));
+
+            Ok(())
+        }
+
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().write::<A>()
+        }
+    }
+
+    /// Increments every `B`, spawns another and gives every entity with a `B` an `A` of it.
+    struct IncrementB;
+
+    impl System for IncrementB {
+        fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+            let ecs = context.ecs;
+            let mut query = ecs.query::<&mut B>().expect("Could not query B");
+            let mut commands = ecs.commands();
+            for (entity, mut b) in query.iter() {
+                b.0 += 1;
+                commands.insert(entity, A(b.0));
+            }
+            commands
+                .spawn()
+                .map_err(|_| SystemError::EntityError)?
+                .insert(B(100));
+
+            Ok(())
+        }
+
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().write::<B>()
+        }
+    }
+
+    /// Adds every `A` up into the `Sum` resource.
+    struct SumA;
+
+    impl System for SumA {
+        fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+            let ecs = context.ecs;
+            let mut sum = ecs.resource_mut::<Sum>().expect("Could not get sum");
+            let mut query = ecs.query::<&A>().expect("Could not query A");
+            for (_, a) in query.iter() {
+                sum.0 += a.0;
+            }
+
+            Ok(())
+        }
+
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().read::<A>().write::<Sum>()
+        }
+    }
+
+    /// Every entity's `A` and `B`, and the sum.
+    type Outcome = (Vec<(Option<A>, Option<B>)>, u32);
+
+    /// The outcome of running the systems three times.
+    fn outcome(parallel: bool) -> Outcome {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<A>()
+            .expect("Could not register component");
+        ecs.register_component::<B>()
+            .expect("Could not register component");
+        ecs.insert_resource(Sum(0));
+        for index in 0..4 {
+            ecs.spawn((A(index),)).expect("Could not spawn entity");
+            ecs.spawn((B(index),)).expect("Could not spawn entity");
+        }
+
+        let mut schedule = Schedule::new();
+        if parallel {
+            schedule.add_parallel_system(Stage::FixedUpdate, "double_a", DoubleA);
+            schedule.add_parallel_system(Stage::FixedUpdate, "increment_b", IncrementB);
+            schedule.add_parallel_system(Stage::FixedUpdate, "sum_a", SumA);
+            assert_eq!(
+                batches(&schedule, Stage::FixedUpdate),
+                vec![vec!["double_a", "increment_b"], vec!["sum_a"]]
+            );
+        } else {
+            schedule.add_system(Stage::FixedUpdate, "double_a", DoubleA);
+            schedule.add_system(Stage::FixedUpdate, "increment_b", IncrementB);
+            schedule.add_system(Stage::FixedUpdate, "sum_a", SumA);
+        }
+
+        for _ in 0..3 {
+            schedule.run(&mut ecs).expect("Could not run schedule");
+        }
+
+        let a = ecs.get_component_vec::<A>().expect("Could not get A");
+        let b = ecs.get_component_vec::<B>().expect("Could not get B");
+        // Entities spawned in the same batch are numbered in the order the systems got to them.
+        let mut components: Vec<(Option<A>, Option<B>)> = (0..ecs.entity_count())
+            .map(|index| (a.get(index).copied(), b.get(index).copied()))
+            .collect();
+        components.sort_by_key(|(a, b)| (a.map(|a| a.0), b.map(|b| b.0)));
+        let sum = ecs.resource::<Sum>().expect("Could not get sum").0;
+
+        (components, sum)
+    }
+
+    #[test]
+    fn parallel_run_matches_sequential_run() {
+        let sequential = outcome(false);
+        for _ in 0..10 {
+            assert_eq!(outcome(true), sequential);
+        }
+    }
+
+    /// Records how many `A` were added, changed and removed since its last run.
+    struct WatchA;
+
+    impl System for WatchA {
+        fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+            let added = context
+                .query_filtered::<&A, Added<A>>()
+                .expect("Could not query A")
+                .iter()
+                .count();
+            let changed = context
+                .query_filtered::<&A, Changed<A>>()
+                .expect("Could not query A")
+                .iter()
+                .count();
+            let removed = context.removed_components::<A>().len();
+            context
+                .ecs
+                .resource_mut::<Seen>()
+                .expect("Could not get seen")
+                .0
+                .push((added, changed, removed));
+
+            Ok(())
+        }
+
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().read::<A>().write::<Seen>()
+        }
+    }
+
+    struct Seen(Vec<(usize, usize, usize)>);
+
+    /// Writes to every `A` on its first run only.
+    struct TouchAOnce(bool);
+
+    impl System for TouchAOnce {
+        fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+            let ecs = context.ecs;
+            if !self.0 {
+                self.0 = true;
+                for (_, mut a) in ecs.query::<&mut A>().expect("Could not query A").iter() {
+                    a.0 += 1;
+                }
+            }
+
+            Ok(())
+        }
+
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().write::<A>()
+        }
+    }
+
+    #[test]
+    fn systems_see_changes_made_after_their_last_run() {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<A>()
+            .expect("Could not register component");
+        ecs.insert_resource(Seen(Vec::new()));
+        let entity = ecs.spawn((A(0),)).expect("Could not spawn entity");
+        ecs.spawn((A(0),)).expect("Could not spawn entity");
+
+        let mut schedule = Schedule::new();
+        schedule.add_parallel_system(Stage::FixedUpdate, "watch", WatchA);
+        schedule.add_parallel_system(Stage::Render, "touch", TouchAOnce(false));
+
+        // Spawned, then touched later in the first frame, then nothing.
+        for _ in 0..3 {
+            schedule.run(&mut ecs).expect("Could not run schedule");
+            ecs.clear_trackers();
+        }
+        // Written to outside of any system, between frames.
+        ecs.get_component::<A>(entity)
+            .expect("Could not get component");
+        schedule.run(&mut ecs).expect("Could not run schedule");
+
+        let seen = ecs.resource::<Seen>().expect("Could not get seen");
+        assert_eq!(seen.0, vec![(2, 2, 0), (0, 2, 0), (0, 0, 0), (0, 1, 0)]);
+    }
+
+    #[test]
+    fn changes_between_runs_are_reported_on_the_next_run_only() {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<A>()
+            .expect("Could not register component");
+        ecs.insert_resource(Seen(Vec::new()));
+        let written = ecs.spawn((A(0),)).expect("Could not spawn entity");
+        let removed = ecs.spawn((A(0),)).expect("Could not spawn entity");
+
+        let mut schedule = Schedule::new();
+        schedule.add_parallel_system(Stage::FixedUpdate, "watch", WatchA);
+        schedule
+            .run_stage(Stage::FixedUpdate, &mut ecs)
+            .expect("Could not run stage");
+
+        // A frame passes without the system running, as when no fixed tick is due.
+        ecs.spawn((A(0),)).expect("Could not spawn entity");
+        ecs.get_component::<A>(written)
+            .expect("Could not get component");
+        ecs.remove_component::<A>(removed)
+            .expect("Could not remove component");
+        ecs.clear_trackers();
+
+        for _ in 0..2 {
+            schedule
+                .run_stage(Stage::FixedUpdate, &mut ecs)
+                .expect("Could not run stage");
+        }
+
+        let seen = ecs.resource::<Seen>().expect("Could not get seen");
+        assert_eq!(seen.0, vec![(2, 2, 0), (1, 2, 1), (0, 0, 0)]);
+    }
+
+    #[test]
+    fn removals_are_dropped_once_every_system_saw_them() {
+        let mut ecs = Ecs::new();
+        ecs.register_component::<A>()
+            .expect("Could not register component");
+        ecs.insert_resource(Seen(Vec::new()));
+        let entity = ecs.spawn((A(0),)).expect("Could not spawn entity");
+
+        let mut schedule = Schedule::new();
+        schedule.add_parallel_system(Stage::FixedUpdate, "watch", WatchA);
+        schedule
+            .run_stage(Stage::FixedUpdate, &mut ecs)
+            .expect("Could not run stage");
+        ecs.remove_component::<A>(entity)
+            .expect("Could not remove component");
+        ecs.clear_trackers();
+        assert_eq!(ecs.removed_components_since::<A>(0), vec![entity]);
+
+        schedule
+            .run_stage(Stage::FixedUpdate, &mut ecs)
+            .expect("Could not run stage");
+        ecs.clear_trackers();
+        assert!(ecs.removed_components_since::<A>(0).is_empty());
+    }
+
+    /// A system of the old shape, holding its own handle to a world and locking it itself.
+    struct CountUpdates<'a> {
+        ecs: &'a Mutex<Ecs>,
+    }
+
+    impl LegacySystem for CountUpdates<'_> {
+        fn update(&mut self) -> Result<(), SystemError> {
+            let ecs = self.ecs.lock().map_err(|_| SystemError::LockError)?;
+            ecs.resource_mut::<Sum>()
+                .map_err(|_| SystemError::ComponentError)?
+                .0 += 1;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn legacy_systems_keep_using_their_own_handle() {
+        let legacy_ecs = Mutex::new(Ecs::new());
+        legacy_ecs
+            .lock()
+            .expect("Could not lock world")
+            .insert_resource(Sum(0));
+        let mut calls = 0;
+
+        let mut schedule = Schedule::new();
+        let counter = CountUpdates { ecs: &legacy_ecs };
+        schedule.add_system(Stage::FixedUpdate, "count", Legacy(counter));
+        let closure = || {
+            calls += 1;
+            Ok(())
+        };
+        schedule.add_system(Stage::Render, "closure", Legacy(closure));
+        let mut ecs = Ecs::new();
+        for _ in 0..3 {
+            schedule.run(&mut ecs).expect("Could not run schedule");
+        }
+        drop(schedule);
+
+        let legacy_ecs = legacy_ecs.lock().expect("Could not lock world");
+        assert_eq!(
+            legacy_ecs.resource::<Sum>().expect("Could not get sum").0,
+            3
+        );
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,117 @@
+use super::{System, SystemAccess, SystemContext, SystemError};
+use crate::{
+    components::{global_transform::GlobalTransform, transform::Transform},
+    ecs::{Changed, Children, Entity, Parent, Query, Without},
+    utils::create_transform_matrix,
+};
+use nalgebra_glm::Mat4;
+use std::collections::HashSet;
+
+type Nodes<'w> = Query<'w, (&'static Transform, Option<&'static Children>)>;
+type Globals<'w> = Query<'w, &'static mut GlobalTransform>;
+
+/// Computes every `GlobalTransform` from the local transforms along the hierarchy. Only
+/// subtrees whose transform or parent changed are recomputed, the rest settle at their current
+/// matrix. Runs once per fixed tick, so the previous matrices are those of the last tick.
+pub struct TransformSystem;
+
+impl TransformSystem {
+    pub fn init() -> Self {
+        Self
+    }
+}
+
+struct Propagation<'w> {
+    dirty: HashSet<Entity>,
+    nodes: Nodes<'w>,
+    globals: Globals<'w>,
+    missing: Vec<(Entity, GlobalTransform)>,
+}
+
+impl Propagation<'_> {
+    fn propagate(&mut self, entity: Entity, parent_matrix: &Mat4, parent_dirty: bool) {
+        let Ok((transform, children)) = self.nodes.get(entity) else {
+            return;
+        };
+        let transform = *transform;
+        let children = children.map(|children| children.entities().to_vec());
+
+        let dirty = parent_dirty || self.dirty.contains(&entity);
+        let matrix = match self.globals.get(entity) {
+            Ok(mut global) if !dirty => {
+                // Only write when needed, so resting entities are not flagged as changed.
+                if global.is_moving() {
+                    global.settle();
+                }
+                global.matrix()
+            }
+            Ok(mut global) => {
+                let matrix = parent_matrix * create_transform_matrix(&transform);
+                global.update(matrix);
+                matrix
+            }
+            Err(_) => {
+                let matrix = parent_matrix * create_transform_matrix(&transform);
+                self.missing.push((entity, GlobalTransform::new(matrix)));
+                matrix
+            }
+        };
+
+        for child in children.into_iter().flatten() {
+            self.propagate(child, &matrix, dirty);
+        }
+    }
+}
+
+impl System for TransformSystem {
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+            .read::<Transform>()
+            .read::<Parent>()
+            .read::<Children>()
+            .write::<GlobalTransform>()
+    }
+
+    fn run(&mut self, context: SystemContext<'_>) -> Result<(), SystemError> {
+        let ecs = context.ecs;
+        let mut dirty: HashSet<Entity> = context
+            .query_filtered::<&Transform, Changed<Transform>>()
+            .expect("Could not query changed transforms")
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        dirty.extend(
+            context
+                .query_filtered::<&Parent, Changed<Parent>>()
+                .expect("Could not query changed parents")
+                .iter()
+                .map(|(entity, _)| entity),
+        );
+        dirty.extend(context.removed_components::<Parent>());
+
+        let roots: Vec<Entity> = ecs
+            .query_filtered::<&Transform, Without<Parent>>()
+            .expect("Could not query root transforms")
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect();
+
+        let mut propagation = Propagation {
+            dirty,
+            nodes: ecs.query().expect("Could not query transforms"),
+            globals: ecs.query().expect("Could not query global transforms"),
+            missing: Vec::new(),
+        };
+        for root in roots {
+            propagation.propagate(root, &Mat4::identity(), false);
+        }
+
+        // Entities without a global transform yet get one once commands are applied.
+        let mut commands = ecs.commands();
+        for (entity, global) in propagation.missing {
+            commands.insert(entity, global);
+        }
+
+        Ok(())
+    }
+}
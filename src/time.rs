@@ -0,0 +1,82 @@
+use crate::constants::{MAX_TICKS_PER_FRAME, TICK_RATE};
+
+/// Frame timing, shared with systems as an ECS resource. All values are in milliseconds.
+///
+/// Elapsed time is collected in an accumulator and paid out in fixed ticks of `fixed_step`,
+/// so the simulation advances at the same rate however long frames take. Whatever is left over
+/// is less than one tick and becomes the interpolation [`Time::alpha`].
+pub struct Time {
+    delta: f32,
+    elapsed: f32,
+    tick_count: u32,
+    fixed_step: f32,
+    max_ticks_per_frame: u32,
+    accumulator: f32,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self::with_fixed_step(TICK_RATE, MAX_TICKS_PER_FRAME)
+    }
+
+    /// Ticks every `fixed_step` milliseconds, running at most `max_ticks_per_frame` ticks per
+    /// frame.
+    pub fn with_fixed_step(fixed_step: f32, max_ticks_per_frame: u32) -> Self {
+        Self {
+            delta: 0.0,
+            elapsed: 0.0,
+            tick_count: 0,
+            fixed_step,
+            max_ticks_per_frame,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Starts a frame at `elapsed` and returns how many fixed ticks to run in it. When a frame
+    /// falls further behind than `max_ticks_per_frame` can catch up on, the excess is dropped,
+    /// slowing the simulation down instead of taking ever longer frames to catch up.
+    pub fn advance_to(&mut self, elapsed: f32) -> u32 {
+        self.delta = elapsed - self.elapsed;
+        self.elapsed = elapsed;
+        self.accumulator += self.delta.max(0.0);
+
+        let due = (self.accumulator / self.fixed_step) as u32;
+        let ticks = due.min(self.max_ticks_per_frame);
+        if ticks < due {
+            self.accumulator %= self.fixed_step;
+        } else {
+            self.accumulator -= ticks as f32 * self.fixed_step;
+        }
+        self.tick_count += ticks;
+
+        ticks
+    }
+
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn tick_count(&self) -> u32 {
+        self.tick_count
+    }
+
+    pub fn fixed_step(&self) -> f32 {
+        self.fixed_step
+    }
+
+    /// How far the frame is between the latest tick and the next one, from 0 to 1. Used to
+    /// blend the previous and current state of moving entities when rendering.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.fixed_step).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,10 @@
 use nalgebra_glm::{self as glm, Mat4, Vec3};
 use std::f32::consts::PI;
 
-use crate::components::transform::Transform;
+use crate::{
+    components::transform::Transform,
+    ecs::{SerializationError, ValueReader, ValueWriter},
+};
 
 pub fn create_empty_buffer(len: usize) -> Vec<u8> {
     let mut buffer: Vec<u8> = Vec::with_capacity(len + 1);
@@ -13,28 +16,6 @@ pub fn degree_to_radian(degree: f32) -> f32 {
     degree * (PI / 180.0)
 }
 
-pub fn point_in_triangle(point: Vec3, triangle: (Vec3, Vec3, Vec3)) -> bool {
-    let (mut a, mut b, mut c) = triangle;
-
-    a -= point;
-    b -= point;
-    c -= point;
-
-    let u = b.cross(&c);
-    let v = c.cross(&a);
-    let w = a.cross(&b);
-
-    if u.dot(&v) < 0.0 {
-        return false;
-    }
-
-    if u.dot(&w) < 0.0 {
-        return false;
-    }
-
-    true
-}
-
 pub fn create_transform_matrix(transform: &Transform) -> Mat4 {
     let position = transform.position();
     let rotation = transform.rotation();
@@ -75,3 +56,13 @@ pub fn tuple_to_vec(tuple: (f32, f32, f32)) -> Vec3 {
 
     Vec3::new(x, y, z)
 }
+
+pub fn write_vec3(writer: &mut ValueWriter, vector: Vec3) {
+    writer.f32(vector.x);
+    writer.f32(vector.y);
+    writer.f32(vector.z);
+}
+
+pub fn read_vec3(reader: &mut ValueReader) -> Result<Vec3, SerializationError> {
+    Ok(Vec3::new(reader.f32()?, reader.f32()?, reader.f32()?))
+}